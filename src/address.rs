@@ -1,23 +1,350 @@
 use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use base58::{FromBase58, FromBase58Error, ToBase58};
+use k256::elliptic_curve::weierstrass::public_key::FromPublicKey;
+use k256::{AffinePoint, PublicKey};
 
 // use ergo_lib::chain::address::{AddressEncoder, NetworkPrefix};
 // use ergo_lib::sigma_protocol::sigma_boolean::ProveDlog;
 // use ergo_lib::serialization::SigmaSerializable;
-use ergo_lib::chain;
+use ergo_lib::chain::Base16DecodedBytes;
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
 use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
 use ergo_lib::ergotree_ir::address::{NetworkPrefix, AddressEncoder};
 use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
-use ergo_lib::ergotree_ir::address::AddressTypePrefix::P2PK;
+
+use crate::utils::blake2b256;
+
+const CHECKSUM_SIZE: usize = 4;
+const PUBLIC_KEY_SIZE: usize = 33;
+const UNCOMPRESSED_PUBLIC_KEY_SIZE: usize = 65;
+const SCRIPT_HASH_SIZE: usize = 24;
+
+/// ErgoTree of P2PK address is `0008cd || public key`
+const P2PK_TREE_PREFIX: [u8; 3] = [0x00, 0x08, 0xcd];
+/// ErgoTree of P2SH address is `prefix || script hash || suffix`
+const P2SH_TREE_PREFIX: [u8; 17] = [
+    0x00, 0xea, 0x02, 0xd1, 0x93, 0xb4, 0xcb, 0xe4, 0xe3, 0x01, 0x0e, 0x04, 0x00, 0x04, 0x30, 0x0e, 0x18,
+];
+const P2SH_TREE_SUFFIX: [u8; 3] = [0xd4, 0x08, 0x01];
+/// ErgoTree header bits which must be zero: reserved flags and the multi-byte header flag
+const ERGO_TREE_HEADER_RESERVED_BITS: u8 = 0xe0;
+
+#[wasm_bindgen]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Network {
+    Mainnet = 0,
+    Testnet = 16,
+}
+
+#[wasm_bindgen]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AddressType {
+    P2PK = 1,
+    P2SH = 2,
+    P2S = 3,
+}
+
+/// Reason why an address string is rejected
+#[derive(PartialEq, Debug, Clone)]
+pub enum AddressError {
+    /// Character outside of the base58 alphabet at the given position
+    InvalidCharacter { character: char, position: usize },
+    /// Checksum does not match the address content (mistyped character)
+    ChecksumMismatch,
+    /// Valid address of another network
+    WrongNetwork { expected: Network, actual: Network },
+    /// Unknown network or address type in the prefix byte
+    UnknownPrefix(u8),
+    /// Content is not a valid public key, script hash or script
+    MalformedContent(String),
+    /// Public key is not a valid secp256k1 point
+    InvalidPublicKey(String),
+}
+
+impl AddressError {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AddressError::InvalidCharacter { .. } => "InvalidCharacter",
+            AddressError::ChecksumMismatch => "ChecksumMismatch",
+            AddressError::WrongNetwork { .. } => "WrongNetwork",
+            AddressError::UnknownPrefix(_) => "UnknownPrefix",
+            AddressError::MalformedContent(_) => "MalformedContent",
+            AddressError::InvalidPublicKey(_) => "InvalidPublicKey",
+        }
+    }
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::InvalidCharacter { character, position } =>
+                write!(f, "Invalid character '{}' at position {}", character, position),
+            AddressError::ChecksumMismatch => write!(f, "Address checksum mismatch"),
+            AddressError::WrongNetwork { expected, actual } =>
+                write!(f, "Address is for {:?}, expected {:?}", actual, expected),
+            AddressError::UnknownPrefix(prefix) => write!(f, "Unknown address prefix: {}", prefix),
+            AddressError::MalformedContent(msg) => write!(f, "Malformed address content: {}", msg),
+            AddressError::InvalidPublicKey(msg) => write!(f, "Invalid public key: {}", msg),
+        }
+    }
+}
+
+impl From<AddressError> for JsValue {
+    fn from(e: AddressError) -> Self {
+        let error = js_sys::Error::new(&e.to_string());
+        error.set_name(e.name());
+        error.into()
+    }
+}
+
+/// Result of `validateAddress`
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct AddressValidation {
+    pub valid: bool,
+    /// Error name (see `AddressError`), if not valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Position of the invalid character
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+}
+
+impl From<Result<(), AddressError>> for AddressValidation {
+    fn from(result: Result<(), AddressError>) -> Self {
+        match result {
+            Ok(()) => AddressValidation {
+                valid: true,
+                reason: None,
+                message: None,
+                position: None,
+            },
+            Err(e) => AddressValidation {
+                valid: false,
+                reason: Some(e.name().to_string()),
+                message: Some(e.to_string()),
+                position: match e {
+                    AddressError::InvalidCharacter { position, .. } => Some(position),
+                    _ => None,
+                },
+            },
+        }
+    }
+}
+
+/// Check the address and return `{valid, reason, message, position}`,
+/// `reason` is one of `AddressError` names
+#[wasm_bindgen(js_name = "validateAddress")]
+pub fn validate_address(address: &str, network: Network) -> Result<JsValue, JsValue> {
+    let result = check_address(address, network);
+    JsValue::from_serde(&AddressValidation::from(result)).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
+
+/// Validate array of address strings in one call, returns array of `validateAddress` results
+#[wasm_bindgen(js_name = "validateAddresses")]
+pub fn validate_addresses(addresses: &JsValue, network: Network) -> Result<JsValue, JsValue> {
+    let addresses: Vec<String> = addresses
+        .into_serde()
+        .map_err(|e| JsValue::from_str(&format!("Expected array of strings: {}", e)))?;
+    let results: Vec<AddressValidation> = addresses
+        .iter()
+        .map(|a| AddressValidation::from(check_address(a, network)))
+        .collect();
+    JsValue::from_serde(&results).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
+
+/// Result of P2PK address derivation for one public key
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct DerivedAddress {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Derive P2PK addresses from array of hex encoded public keys (compressed or uncompressed)
+/// in one call, returns array of `{address}` or `{error}`
+#[wasm_bindgen(js_name = "addressesFromPublicKeys")]
+pub fn addresses_from_public_keys(public_keys: &JsValue, network: Network) -> Result<JsValue, JsValue> {
+    let public_keys: Vec<String> = public_keys
+        .into_serde()
+        .map_err(|e| JsValue::from_str(&format!("Expected array of strings: {}", e)))?;
+    let results: Vec<DerivedAddress> = public_keys
+        .into_iter()
+        .map(|pk| {
+            let address = Base16DecodedBytes::try_from(pk)
+                .map_err(|_| AddressError::InvalidPublicKey("not a valid hex string".to_string()))
+                .and_then(|bytes| p2pk_address(&bytes.0, network));
+            match address {
+                Ok(address) => DerivedAddress {
+                    address: Some(address),
+                    error: None,
+                },
+                Err(e) => DerivedAddress {
+                    address: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .collect();
+    JsValue::from_serde(&results).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
+
+fn p2pk_address(pub_key: &[u8], network: Network) -> Result<String, AddressError> {
+    Ok(RawAddress::p2pk(pub_key, network)?.encode())
+}
+
+pub(crate) fn check_address(address: &str, network: Network) -> Result<(), AddressError> {
+    RawAddress::decode(address).and_then(|raw| raw.check_network(network))
+}
+
+impl Network {
+    fn from_prefix(prefix: u8) -> Option<Network> {
+        match prefix {
+            0 => Some(Network::Mainnet),
+            16 => Some(Network::Testnet),
+            _ => None,
+        }
+    }
+}
+
+impl AddressType {
+    fn from_prefix(prefix: u8) -> Option<AddressType> {
+        match prefix {
+            1 => Some(AddressType::P2PK),
+            2 => Some(AddressType::P2SH),
+            3 => Some(AddressType::P2S),
+            _ => None,
+        }
+    }
+}
+
+/// Decoded address: `network + type` prefix byte, content and checksum
+#[derive(PartialEq, Debug, Clone)]
+struct RawAddress {
+    network: Network,
+    address_type: AddressType,
+    content: Vec<u8>,
+}
+
+impl RawAddress {
+    fn p2pk(pub_key: &[u8], network: Network) -> Result<RawAddress, AddressError> {
+        Ok(RawAddress {
+            network,
+            address_type: AddressType::P2PK,
+            content: compress_public_key(pub_key)?,
+        })
+    }
+
+    fn decode(address: &str) -> Result<RawAddress, AddressError> {
+        let bytes = address.from_base58().map_err(|e| match e {
+            FromBase58Error::InvalidBase58Character(character, position) =>
+                AddressError::InvalidCharacter { character, position },
+            FromBase58Error::InvalidBase58Length =>
+                AddressError::MalformedContent("invalid base58 length".to_string()),
+        })?;
+        if bytes.len() < 1 + CHECKSUM_SIZE {
+            return Err(AddressError::MalformedContent("address is too short".to_string()));
+        }
+
+        let (body, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+        if blake2b256(body)[..CHECKSUM_SIZE] != checksum[..] {
+            return Err(AddressError::ChecksumMismatch);
+        }
+
+        let network = Network::from_prefix(body[0] & 0xF0).ok_or(AddressError::UnknownPrefix(body[0]))?;
+        let address_type = AddressType::from_prefix(body[0] & 0x0F).ok_or(AddressError::UnknownPrefix(body[0]))?;
+
+        let raw = RawAddress {
+            network,
+            address_type,
+            content: body[1..].to_vec(),
+        };
+        raw.check_content()?;
+        Ok(raw)
+    }
+
+    fn check_content(&self) -> Result<(), AddressError> {
+        match self.address_type {
+            AddressType::P2PK => ProveDlog::sigma_parse_bytes(self.content.clone())
+                .map(|_| ())
+                .map_err(|_| AddressError::MalformedContent("invalid public key".to_string())),
+            AddressType::P2SH if self.content.len() != SCRIPT_HASH_SIZE =>
+                Err(AddressError::MalformedContent(format!("script hash must be {} bytes", SCRIPT_HASH_SIZE))),
+            AddressType::P2SH => Ok(()),
+            AddressType::P2S => ErgoTree::sigma_parse_bytes(self.content.clone())
+                .map(|_| ())
+                .map_err(|e| AddressError::MalformedContent(format!("invalid script: {:?}", e))),
+        }
+    }
+
+    fn check_network(&self, network: Network) -> Result<(), AddressError> {
+        if self.network != network {
+            return Err(AddressError::WrongNetwork {
+                expected: network,
+                actual: self.network,
+            });
+        }
+        Ok(())
+    }
+
+    fn encode(&self) -> String {
+        let mut bytes = vec![self.network as u8 + self.address_type as u8];
+        bytes.extend_from_slice(&self.content);
+        let checksum = blake2b256(&bytes);
+        bytes.extend_from_slice(&checksum[..CHECKSUM_SIZE]);
+        bytes.to_base58()
+    }
+
+    fn ergo_tree_bytes(&self) -> Vec<u8> {
+        match self.address_type {
+            AddressType::P2PK => [&P2PK_TREE_PREFIX[..], &self.content].concat(),
+            AddressType::P2SH => [&P2SH_TREE_PREFIX[..], &self.content, &P2SH_TREE_SUFFIX[..]].concat(),
+            AddressType::P2S => self.content.clone(),
+        }
+    }
+
+    /// Recognize P2PK and P2SH templates, any other tree is P2S
+    fn from_ergo_tree_bytes(tree: &[u8], network: Network) -> RawAddress {
+        let p2sh_len = P2SH_TREE_PREFIX.len() + SCRIPT_HASH_SIZE + P2SH_TREE_SUFFIX.len();
+
+        let (address_type, content) = if tree.len() == P2PK_TREE_PREFIX.len() + PUBLIC_KEY_SIZE
+            && tree.starts_with(&P2PK_TREE_PREFIX)
+        {
+            (AddressType::P2PK, tree[P2PK_TREE_PREFIX.len()..].to_vec())
+        } else if tree.len() == p2sh_len
+            && tree.starts_with(&P2SH_TREE_PREFIX)
+            && tree.ends_with(&P2SH_TREE_SUFFIX)
+        {
+            (AddressType::P2SH, tree[P2SH_TREE_PREFIX.len()..P2SH_TREE_PREFIX.len() + SCRIPT_HASH_SIZE].to_vec())
+        } else {
+            (AddressType::P2S, tree.to_vec())
+        };
+
+        RawAddress {
+            network,
+            address_type,
+            content,
+        }
+    }
+}
 
 #[wasm_bindgen]
 pub struct Address {
     address: String,
+    raw: RawAddress,
 }
 
 #[wasm_bindgen]
 impl Address {
     pub fn get_addr(&self) -> String {
-        return self.address.clone();
+        self.address.clone()
     }
 
     pub fn validate(address: &str) -> bool {
@@ -29,26 +356,141 @@ impl Address {
         }
     }
 
-    pub fn from_public_key(pub_key: &[u8]) -> Address {
-        let mut content_bytes: Vec<u8> = vec![];
-        content_bytes.extend_from_slice(pub_key);
+    /// Parse address of any type and network, checking the checksum
+    pub fn from_base58(address: &str) -> Result<Address, JsValue> {
+        Ok(Address {
+            address: address.to_string(),
+            raw: RawAddress::decode(address)?,
+        })
+    }
 
-        let p2pk_address = ergo_lib::ergotree_ir::address::Address::P2PK(ProveDlog::sigma_parse_bytes(content_bytes).unwrap());
-        let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
+    /// P2PK mainnet address of compressed (33 bytes) or uncompressed (65 bytes) secp256k1 public key
+    pub fn from_public_key(pub_key: &[u8]) -> Result<Address, JsValue> {
+        Ok(Address::from_raw(RawAddress::p2pk(pub_key, Network::Mainnet)?))
+    }
+
+    /// Address protecting boxes with the given serialized ErgoTree.
+    /// P2PK and P2SH trees give the corresponding address types, any other tree gives P2S.
+    pub fn from_ergo_tree(ergo_tree: &[u8], network: Network) -> Result<Address, JsValue> {
+        parse_ergo_tree(ergo_tree)?;
+        let raw = RawAddress::from_ergo_tree_bytes(ergo_tree, network);
+        raw.check_content()?;
+        Ok(Address::from_raw(raw))
+    }
+
+    /// P2S address of the script (serialized ErgoTree)
+    pub fn p2s_from_ergo_tree(ergo_tree: &[u8], network: Network) -> Result<Address, JsValue> {
+        parse_ergo_tree(ergo_tree)?;
+        let raw = RawAddress {
+            network,
+            address_type: AddressType::P2S,
+            content: ergo_tree.to_vec(),
+        };
+        Ok(Address::from_raw(raw))
+    }
+
+    pub fn p2s_from_ergo_tree_hex(ergo_tree: &str, network: Network) -> Result<Address, JsValue> {
+        Address::p2s_from_ergo_tree(&ergo_tree_from_hex(ergo_tree)?, network)
+    }
+
+    /// P2SH address of the script (serialized ErgoTree), committing to the script hash only.
+    /// As in the node, the hash is taken over the serialized proposition with constants
+    /// substituted, so it does not depend on the tree header or constant segregation.
+    pub fn p2sh_from_ergo_tree(ergo_tree: &[u8], network: Network) -> Result<Address, JsValue> {
+        let proposition = parse_ergo_tree(ergo_tree)?
+            .proposition()
+            .map_err(|e| JsValue::from_str(&format!("Invalid ErgoTree: {:?}", e)))?;
+        let raw = RawAddress {
+            network,
+            address_type: AddressType::P2SH,
+            content: blake2b256(&proposition.sigma_serialize_bytes())[..SCRIPT_HASH_SIZE].to_vec(),
+        };
+        Ok(Address::from_raw(raw))
+    }
+
+    pub fn p2sh_from_ergo_tree_hex(ergo_tree: &str, network: Network) -> Result<Address, JsValue> {
+        Address::p2sh_from_ergo_tree(&ergo_tree_from_hex(ergo_tree)?, network)
+    }
+
+    pub fn address_type(&self) -> AddressType {
+        self.raw.address_type
+    }
+
+    pub fn network(&self) -> Network {
+        self.raw.network
+    }
+
+    /// Compressed public key (33 bytes) of P2PK address
+    pub fn public_key(&self) -> Option<Vec<u8>> {
+        match self.raw.address_type {
+            AddressType::P2PK => Some(self.raw.content.clone()),
+            _ => None,
+        }
+    }
+
+    /// Script hash (first 24 bytes of blake2b256 of the script) of P2SH address
+    pub fn script_hash(&self) -> Option<Vec<u8>> {
+        match self.raw.address_type {
+            AddressType::P2SH => Some(self.raw.content.clone()),
+            _ => None,
+        }
+    }
+
+    /// Serialized ErgoTree protecting boxes sent to this address
+    pub fn ergo_tree(&self) -> Vec<u8> {
+        self.raw.ergo_tree_bytes()
+    }
+}
+
+fn parse_ergo_tree(ergo_tree: &[u8]) -> Result<ErgoTree, JsValue> {
+    // unsupported script bodies are kept as raw bytes by the parser, the header is checked here
+    if ergo_tree.first().is_some_and(|header| header & ERGO_TREE_HEADER_RESERVED_BITS != 0) {
+        return Err(JsValue::from_str("Invalid ErgoTree: unsupported header"));
+    }
+    ErgoTree::sigma_parse_bytes(ergo_tree.to_vec())
+        .map_err(|e| JsValue::from_str(&format!("Invalid ErgoTree: {:?}", e)))
+}
+
+fn ergo_tree_from_hex(ergo_tree: &str) -> Result<Vec<u8>, JsValue> {
+    Base16DecodedBytes::try_from(ergo_tree.to_string())
+        .map(|bytes| bytes.0)
+        .map_err(|_| JsValue::from_str("ErgoTree is not a valid hex string"))
+}
+
+/// Check that the key is a point on the curve and return its compressed encoding
+fn compress_public_key(pub_key: &[u8]) -> Result<Vec<u8>, AddressError> {
+    if pub_key.len() != PUBLIC_KEY_SIZE && pub_key.len() != UNCOMPRESSED_PUBLIC_KEY_SIZE {
+        return Err(AddressError::InvalidPublicKey(format!(
+            "expected {} or {} bytes, got {}",
+            PUBLIC_KEY_SIZE,
+            UNCOMPRESSED_PUBLIC_KEY_SIZE,
+            pub_key.len()
+        )));
+    }
+
+    let encoded = PublicKey::from_bytes(pub_key)
+        .ok_or_else(|| AddressError::InvalidPublicKey("invalid SEC1 encoding".to_string()))?;
+    let point: Option<AffinePoint> = AffinePoint::from_public_key(&encoded).into();
+    let point = point.ok_or_else(|| AddressError::InvalidPublicKey("point is not on the curve".to_string()))?;
+
+    Ok(point.to_pubkey(true).as_bytes().to_vec())
+}
+
+impl Address {
+    // Content of `raw` must already be checked
+    fn from_raw(raw: RawAddress) -> Address {
         Address {
-            address: encoder.address_to_str(&p2pk_address),
+            address: raw.encode(),
+            raw,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::convert::TryFrom;
-    use wasm_bindgen::JsValue;
-
     #[test]
     pub fn address_validation() {
-        let result = super::Address::validate(&"we");
-        assert!(result);
+        assert!(super::Address::validate("9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV"));
+        assert!(!super::Address::validate("we"));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,175 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::collections::BTreeMap;
+
+use crate::address::{check_address, Network};
+
+pub const ADDRESS_BOOK_VERSION: u32 = 1;
+
+/// Number of leading and trailing characters users usually compare by eye
+const LOOKALIKE_AFFIX_SIZE: usize = 4;
+/// Addresses this many edits apart (or closer) are considered similar
+const MAX_SIMILAR_DISTANCE: usize = 3;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct AddressBookEntry {
+    pub address: String,
+    pub label: String,
+    /// "mainnet" or "testnet"
+    pub network: String,
+}
+
+/// Canonical JSON of the address book: entries sorted by address
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+struct AddressBookJson {
+    version: u32,
+    entries: Vec<AddressBookEntry>,
+}
+
+/// Labeled and validated addresses
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct AddressBook {
+    entries: BTreeMap<String, AddressBookEntry>,
+}
+
+#[wasm_bindgen]
+impl AddressBook {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> AddressBook {
+        AddressBook::default()
+    }
+
+    /// Add the address or replace the label of the saved one
+    pub fn add(&mut self, address: &str, label: &str, network: Network) -> Result<(), JsValue> {
+        check_address(address, network)?;
+        self.entries.insert(address.to_string(), AddressBookEntry {
+            address: address.to_string(),
+            label: label.to_string(),
+            network: network_name(network).to_string(),
+        });
+        Ok(())
+    }
+
+    /// Returns false if the address is not in the book
+    pub fn remove(&mut self, address: &str) -> bool {
+        self.entries.remove(address).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Label of the saved address
+    pub fn label(&self, address: &str) -> Option<String> {
+        self.entries.get(address).map(|e| e.label.clone())
+    }
+
+    /// All entries (`[{address, label, network}]`) sorted by address
+    pub fn entries(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.entries.values().collect::<Vec<_>>())
+    }
+
+    /// Entries with the label containing the query (case insensitive) or the address starting with it
+    pub fn search(&self, query: &str) -> Result<JsValue, JsValue> {
+        let query_lowercase = query.to_lowercase();
+        let found: Vec<&AddressBookEntry> = self.entries
+            .values()
+            .filter(|e| e.label.to_lowercase().contains(&query_lowercase) || e.address.starts_with(query))
+            .collect();
+        to_js(&found)
+    }
+
+    /// Saved entries which look like the address but are not the same address.
+    /// Non-empty result for a pasted address is a sign of address poisoning.
+    pub fn similar(&self, address: &str) -> Result<JsValue, JsValue> {
+        to_js(&self.similar_entries(address))
+    }
+
+    /// Canonical JSON: `{version, entries}` with entries sorted by address
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&AddressBookJson {
+            version: ADDRESS_BOOK_VERSION,
+            entries: self.entries.values().cloned().collect(),
+        }).unwrap()
+    }
+
+    /// Parse and validate JSON produced by `to_json`
+    pub fn from_json(json: &str) -> Result<AddressBook, JsValue> {
+        let parsed: AddressBookJson = serde_json::from_str(json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid address book: {}", e)))?;
+        if parsed.version != ADDRESS_BOOK_VERSION {
+            return Err(JsValue::from_str(&format!("Unsupported address book version: {}", parsed.version)));
+        }
+
+        let mut book = AddressBook::new();
+        for entry in parsed.entries {
+            let network = network_from_name(&entry.network)
+                .ok_or_else(|| JsValue::from_str(&format!("Unknown network: {}", entry.network)))?;
+            if book.entries.contains_key(&entry.address) {
+                return Err(JsValue::from_str(&format!("Duplicate address: {}", entry.address)));
+            }
+            book.add(&entry.address, &entry.label, network)?;
+        }
+        Ok(book)
+    }
+}
+
+impl AddressBook {
+    pub fn similar_entries(&self, address: &str) -> Vec<&AddressBookEntry> {
+        self.entries
+            .values()
+            .filter(|e| e.address != address && looks_alike(&e.address, address))
+            .collect()
+    }
+}
+
+fn looks_alike(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len() <= LOOKALIKE_AFFIX_SIZE * 2 || b.len() <= LOOKALIKE_AFFIX_SIZE * 2 {
+        return false;
+    }
+
+    let same_affixes = a[..LOOKALIKE_AFFIX_SIZE] == b[..LOOKALIKE_AFFIX_SIZE]
+        && a[a.len() - LOOKALIKE_AFFIX_SIZE..] == b[b.len() - LOOKALIKE_AFFIX_SIZE..];
+    same_affixes || edit_distance(&a, &b) <= MAX_SIMILAR_DISTANCE
+}
+
+/// Levenshtein distance
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + if ca == cb { 0 } else { 1 };
+            row[j + 1] = substitution.min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+fn network_name(network: Network) -> &'static str {
+    match network {
+        Network::Mainnet => "mainnet",
+        Network::Testnet => "testnet",
+    }
+}
+
+fn network_from_name(name: &str) -> Option<Network> {
+    match name {
+        "mainnet" => Some(Network::Mainnet),
+        "testnet" => Some(Network::Testnet),
+        _ => None,
+    }
+}
+
+fn to_js<T: serde::Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    JsValue::from_serde(value).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
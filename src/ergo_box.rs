@@ -0,0 +1,198 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::convert::TryFrom;
+
+use ergo_lib::chain;
+use ergo_lib::chain::ergo_box::{BoxValue, NonMandatoryRegisters};
+use ergo_lib::chain::token::Token;
+use ergo_lib::chain::transaction::TxId;
+use ergo_lib::chain::{Base16DecodedBytes, Base16EncodedBytes, Digest32};
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+use crate::transaction::AssetValue;
+use crate::utils::digest32_to_hex;
+
+/// Box (UTXO) with id, transaction id and index in transaction outputs
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone)]
+pub struct ErgoBox(pub(crate) chain::ergo_box::ErgoBox);
+
+#[wasm_bindgen]
+impl ErgoBox {
+    /// JSON representation
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.0).map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+
+    /// Parse from JSON representation
+    pub fn from_json(json: &JsValue) -> Result<ErgoBox, JsValue> {
+        json.into_serde::<chain::ergo_box::ErgoBox>()
+            .map(ErgoBox)
+            .map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+
+    /// Box id (hex encoded)
+    pub fn box_id(&self) -> String {
+        digest32_to_hex(&self.0.box_id().0)
+    }
+
+    /// Id of the transaction which created this box (hex encoded)
+    pub fn tx_id(&self) -> String {
+        digest32_to_hex(&self.0.transaction_id.0)
+    }
+
+    /// Index in the outputs of the transaction which created this box
+    pub fn index(&self) -> u16 {
+        self.0.index
+    }
+
+    /// Amount of nanoERGs
+    pub fn value(&self) -> u64 {
+        u64::from(self.0.value)
+    }
+
+    pub fn creation_height(&self) -> u32 {
+        self.0.creation_height
+    }
+
+    /// Tokens as array of `{tokenId, amount}`
+    pub fn tokens(&self) -> Result<JsValue, JsValue> {
+        tokens_to_js(&self.0.tokens)
+    }
+
+    /// Additional registers (R4-R9) as map of hex encoded constants
+    pub fn registers(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.0.additional_registers).map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+}
+
+impl From<chain::ergo_box::ErgoBox> for ErgoBox {
+    fn from(b: chain::ergo_box::ErgoBox) -> Self {
+        ErgoBox(b)
+    }
+}
+
+impl From<ErgoBox> for chain::ergo_box::ErgoBox {
+    fn from(b: ErgoBox) -> Self {
+        b.0
+    }
+}
+
+/// Box that is not yet part of a transaction (no id)
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone)]
+pub struct ErgoBoxCandidate(pub(crate) chain::ergo_box::ErgoBoxCandidate);
+
+#[wasm_bindgen]
+impl ErgoBoxCandidate {
+    /// JSON representation
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&ErgoBoxCandidateJson::from(&self.0)).map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+
+    /// Parse from JSON representation
+    pub fn from_json(json: &JsValue) -> Result<ErgoBoxCandidate, JsValue> {
+        let json = json.into_serde::<ErgoBoxCandidateJson>()
+            .map_err(|e| JsValue::from_str(&format!("{}", e)))?;
+        let bytes = Base16DecodedBytes::try_from(json.ergo_tree)
+            .map_err(|_| JsValue::from_str("ErgoTree is not a valid hex string"))?;
+        let ergo_tree = ErgoTree::sigma_parse_bytes(bytes.0)
+            .map_err(|e| JsValue::from_str(&format!("Invalid ErgoTree: {}", e)))?;
+
+        Ok(ErgoBoxCandidate(chain::ergo_box::ErgoBoxCandidate {
+            value: json.value,
+            ergo_tree,
+            tokens: json.assets,
+            additional_registers: json.additional_registers,
+            creation_height: json.creation_height,
+        }))
+    }
+
+    /// Amount of nanoERGs
+    pub fn value(&self) -> u64 {
+        u64::from(self.0.value)
+    }
+
+    pub fn creation_height(&self) -> u32 {
+        self.0.creation_height
+    }
+
+    /// Tokens as array of `{tokenId, amount}`
+    pub fn tokens(&self) -> Result<JsValue, JsValue> {
+        tokens_to_js(&self.0.tokens)
+    }
+
+    /// Additional registers (R4-R9) as map of hex encoded constants
+    pub fn registers(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.0.additional_registers).map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+
+    /// Box created from this candidate as output `index` of transaction `tx_id`
+    pub fn to_box(&self, tx_id: &str, index: u16) -> Result<ErgoBox, JsValue> {
+        let tx_id = tx_id_from_hex(tx_id)?;
+        Ok(ErgoBox(chain::ergo_box::ErgoBox::from_box_candidate(&self.0, tx_id, index)))
+    }
+
+    /// Id of the box created from this candidate as output `index` of transaction `tx_id`
+    pub fn box_id(&self, tx_id: &str, index: u16) -> Result<String, JsValue> {
+        self.to_box(tx_id, index).map(|b| b.box_id())
+    }
+}
+
+/// JSON layout of a box candidate, same as the node's box without `boxId`, `transactionId` and `index`
+#[derive(Serialize, Deserialize)]
+struct ErgoBoxCandidateJson {
+    value: BoxValue,
+    #[serde(rename = "ergoTree")]
+    ergo_tree: String,
+    assets: Vec<Token>,
+    #[serde(rename = "additionalRegisters")]
+    additional_registers: NonMandatoryRegisters,
+    #[serde(rename = "creationHeight")]
+    creation_height: u32,
+}
+
+impl From<&chain::ergo_box::ErgoBoxCandidate> for ErgoBoxCandidateJson {
+    fn from(b: &chain::ergo_box::ErgoBoxCandidate) -> Self {
+        ErgoBoxCandidateJson {
+            value: b.value,
+            ergo_tree: Base16EncodedBytes::new(b.ergo_tree.sigma_serialize_bytes().as_slice()).into(),
+            assets: b.tokens.clone(),
+            additional_registers: b.additional_registers.clone(),
+            creation_height: b.creation_height,
+        }
+    }
+}
+
+impl From<chain::ergo_box::ErgoBoxCandidate> for ErgoBoxCandidate {
+    fn from(b: chain::ergo_box::ErgoBoxCandidate) -> Self {
+        ErgoBoxCandidate(b)
+    }
+}
+
+impl From<ErgoBoxCandidate> for chain::ergo_box::ErgoBoxCandidate {
+    fn from(b: ErgoBoxCandidate) -> Self {
+        b.0
+    }
+}
+
+fn tx_id_from_hex(tx_id: &str) -> Result<TxId, JsValue> {
+    let bytes = Base16DecodedBytes::try_from(tx_id.to_string())
+        .map_err(|_| JsValue::from_str("Transaction id is not a valid hex string"))?;
+    let digest = Digest32::try_from(bytes)
+        .map_err(|_| JsValue::from_str("Transaction id must be 32 bytes"))?;
+    Ok(TxId(digest))
+}
+
+fn tokens_to_js(tokens: &[chain::token::Token]) -> Result<JsValue, JsValue> {
+    let assets: Vec<AssetValue> = tokens
+        .iter()
+        .map(|t| AssetValue {
+            token_id: digest32_to_hex(&t.token_id.0),
+            amount: u64::from(t.amount).to_string(),
+        })
+        .collect();
+    JsValue::from_serde(&assets).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
@@ -0,0 +1,318 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use ergo_lib::chain;
+use ergo_lib::chain::transaction::Input;
+use ergo_lib::chain::transaction::input::prover_result::ProverResult;
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use zeroize::Zeroizing;
+
+use crate::address::Address;
+use crate::message_signing::{dlog_secret_from_hex, sign_message_with};
+use crate::transaction::{Transaction, UnsignedTransaction};
+
+const STATIC_URI_SCHEME: &str = "ergopay:";
+const DYNAMIC_URI_SCHEME: &str = "ergopay://";
+/// Replaced in the dynamic request URL by the address of the wallet
+const ADDRESS_PLACEHOLDER: &str = "#P2PK_ADDRESS#";
+
+/// SigmaBoolean opcode of `proveDlog`
+const PROVE_DLOG_OPCODE: u8 = 0xcd;
+const PUBLIC_KEY_SIZE: usize = 33;
+
+#[wasm_bindgen]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MessageSeverity {
+    None,
+    Information,
+    Warning,
+    Error,
+}
+
+/// Response of the dApp to the dynamic ErgoPay request (EIP-20)
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+struct ErgoPayResponse {
+    /// Reduced transaction, base64url encoded
+    #[serde(rename = "reducedTx")]
+    reduced_tx: Option<String>,
+    address: Option<String>,
+    message: Option<String>,
+    #[serde(rename = "messageSeverity")]
+    message_severity: Option<String>,
+    #[serde(rename = "replyTo")]
+    reply_to: Option<String>,
+}
+
+/// ErgoPay signing request (EIP-20): reduced transaction and the dApp message.
+///
+/// Only inputs reduced to a single `proveDlog` can be signed, which covers
+/// boxes protected by P2PK addresses.
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone)]
+pub struct ErgoPayRequest {
+    reduced_tx: Option<ReducedTransaction>,
+    address: Option<String>,
+    message: Option<String>,
+    severity: MessageSeverity,
+    reply_to: Option<String>,
+}
+
+#[wasm_bindgen]
+impl ErgoPayRequest {
+    /// Parse static `ergopay:<base64url reduced tx>` URI
+    pub fn from_uri(uri: &str) -> Result<ErgoPayRequest, JsValue> {
+        if is_dynamic_uri(uri) {
+            return Err(JsValue::from_str("Dynamic ErgoPay URI must be fetched with ergoPayRequest"));
+        }
+        let payload = strip_scheme(uri, STATIC_URI_SCHEME)
+            .ok_or_else(|| JsValue::from_str("ErgoPay URI must start with 'ergopay:'"))?;
+
+        Ok(ErgoPayRequest {
+            reduced_tx: Some(ReducedTransaction::from_base64(payload)?),
+            address: None,
+            message: None,
+            severity: MessageSeverity::None,
+            reply_to: None,
+        })
+    }
+
+    /// Parse the JSON response of the dApp (`{reducedTx, address, message, messageSeverity, replyTo}`)
+    pub fn from_json(json: &JsValue) -> Result<ErgoPayRequest, JsValue> {
+        let response: ErgoPayResponse = match json.as_string() {
+            Some(text) => serde_json::from_str(&text).map_err(|e| JsValue::from_str(&format!("{}", e)))?,
+            None => json.into_serde().map_err(|e| JsValue::from_str(&format!("{}", e)))?,
+        };
+
+        let severity = match response.message_severity.as_deref() {
+            None | Some("NONE") => MessageSeverity::None,
+            Some("INFORMATION") => MessageSeverity::Information,
+            Some("WARNING") => MessageSeverity::Warning,
+            Some("ERROR") => MessageSeverity::Error,
+            Some(s) => return Err(JsValue::from_str(&format!("Unknown message severity: {}", s))),
+        };
+        let reduced_tx = match response.reduced_tx {
+            Some(tx) => Some(ReducedTransaction::from_base64(&tx)?),
+            None => None,
+        };
+
+        Ok(ErgoPayRequest {
+            reduced_tx,
+            address: response.address,
+            message: response.message,
+            severity,
+            reply_to: response.reply_to,
+        })
+    }
+
+    /// Message of the dApp to show to the user
+    pub fn message(&self) -> Option<String> {
+        self.message.clone()
+    }
+
+    pub fn severity(&self) -> MessageSeverity {
+        self.severity
+    }
+
+    /// Address the transaction is built for
+    pub fn address(&self) -> Option<String> {
+        self.address.clone()
+    }
+
+    /// URL to POST `replyBody` to after the transaction is submitted
+    pub fn reply_to(&self) -> Option<String> {
+        self.reply_to.clone()
+    }
+
+    /// Request without transaction only carries the message (e.g. an error)
+    pub fn has_transaction(&self) -> bool {
+        self.reduced_tx.is_some()
+    }
+
+    pub fn unsigned_tx(&self) -> Result<UnsignedTransaction, JsValue> {
+        Ok(self.reduced()?.unsigned_tx.clone())
+    }
+
+    /// Sign the reduced transaction with the secret keys (hex encoded)
+    pub fn sign(&self, secret_keys: Box<[JsValue]>) -> Result<Transaction, JsValue> {
+        let secrets = secret_keys
+            .iter()
+            .map(|x| {
+                let hex = Zeroizing::new(x.as_string().ok_or_else(|| JsValue::from_str("Secret key must be a string"))?);
+                dlog_secret_from_hex(&hex)
+            })
+            .collect::<Result<Vec<DlogProverInput>, JsValue>>()?;
+        self.reduced()?.sign(&secrets)
+    }
+
+    /// Body of the reply to the dApp: `{txId}`
+    pub fn reply_body(tx: &Transaction) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&serde_json::json!({ "txId": tx.tx_id() }))
+            .map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+}
+
+impl ErgoPayRequest {
+    fn reduced(&self) -> Result<&ReducedTransaction, JsValue> {
+        self.reduced_tx
+            .as_ref()
+            .ok_or_else(|| JsValue::from_str("ErgoPay request has no transaction"))
+    }
+}
+
+/// Resolve ErgoPay URI into a request.
+/// Static URIs are decoded directly. For dynamic URIs (`ergopay://...`)
+/// `fetch(url)` is called with the https URL and must return (a promise of)
+/// the dApp response as JSON object or string. `address` replaces `#P2PK_ADDRESS#` in the URL.
+#[wasm_bindgen(js_name = "ergoPayRequest")]
+pub async fn ergo_pay_request(
+    uri: String,
+    address: Option<String>,
+    fetch: js_sys::Function,
+) -> Result<ErgoPayRequest, JsValue> {
+    if !is_dynamic_uri(&uri) {
+        return ErgoPayRequest::from_uri(&uri);
+    }
+
+    let url = dynamic_request_url(&uri, address.as_deref())?;
+    let result = fetch.call1(&JsValue::NULL, &JsValue::from_str(&url))?;
+    let response = JsFuture::from(js_sys::Promise::resolve(&result)).await?;
+
+    ErgoPayRequest::from_json(&response)
+}
+
+fn is_dynamic_uri(uri: &str) -> bool {
+    strip_scheme(uri, DYNAMIC_URI_SCHEME).is_some()
+}
+
+fn strip_scheme<'a>(uri: &'a str, scheme: &str) -> Option<&'a str> {
+    match uri.get(..scheme.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(scheme) => Some(&uri[scheme.len()..]),
+        _ => None,
+    }
+}
+
+fn dynamic_request_url(uri: &str, address: Option<&str>) -> Result<String, JsValue> {
+    let path = strip_scheme(uri, DYNAMIC_URI_SCHEME).unwrap();
+    let url = format!("https://{}", path);
+    if !url.contains(ADDRESS_PLACEHOLDER) {
+        return Ok(url);
+    }
+    let address = address.ok_or_else(|| JsValue::from_str("ErgoPay request requires the wallet address"))?;
+    // base58 only, so it can be put into the URL as is
+    Address::from_base58(address)?;
+    Ok(url.replace(ADDRESS_PLACEHOLDER, address))
+}
+
+/// Transaction with inputs reduced to sigma propositions, as serialized by the node:
+/// `VLQ(len) || bytes to sign || (SigmaBoolean || VLQ(cost)) per input || VLQ(cost)`
+#[derive(PartialEq, Debug, Clone)]
+struct ReducedTransaction {
+    bytes_to_sign: Vec<u8>,
+    unsigned_tx: UnsignedTransaction,
+    /// Public key of `proveDlog` each input is reduced to
+    input_keys: Vec<Vec<u8>>,
+}
+
+impl ReducedTransaction {
+    fn from_base64(encoded: &str) -> Result<ReducedTransaction, JsValue> {
+        let bytes = base64::decode_config(encoded.trim_end_matches('='), base64::URL_SAFE_NO_PAD)
+            .map_err(|e| JsValue::from_str(&format!("Reduced transaction is not valid base64: {}", e)))?;
+        ReducedTransaction::from_bytes(&bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<ReducedTransaction, JsValue> {
+        let truncated = || JsValue::from_str("Reduced transaction is truncated");
+        let mut pos = 0;
+
+        let len = read_vlq(bytes, &mut pos)
+            .and_then(|len| usize::try_from(len).ok())
+            .ok_or_else(truncated)?;
+        let end = pos.checked_add(len).ok_or_else(truncated)?;
+        let bytes_to_sign = bytes.get(pos..end).ok_or_else(truncated)?.to_vec();
+        pos = end;
+        let unsigned_tx = UnsignedTransaction::from_bytes(&bytes_to_sign)?;
+
+        let mut input_keys = vec![];
+        for _ in 0..unsigned_tx.0.inputs.len() {
+            match bytes.get(pos) {
+                Some(&PROVE_DLOG_OPCODE) => {}
+                Some(opcode) => return Err(JsValue::from_str(&format!(
+                    "Only inputs reduced to proveDlog are supported, got opcode {}", opcode
+                ))),
+                None => return Err(truncated()),
+            }
+            let end = pos.checked_add(1 + PUBLIC_KEY_SIZE).ok_or_else(truncated)?;
+            let pk = bytes.get(pos + 1..end).ok_or_else(truncated)?;
+            input_keys.push(pk.to_vec());
+            pos = end;
+            // reduction cost
+            read_vlq(bytes, &mut pos).ok_or_else(truncated)?;
+        }
+        // total cost
+        read_vlq(bytes, &mut pos).ok_or_else(truncated)?;
+        if pos != bytes.len() {
+            return Err(JsValue::from_str("Reduced transaction has unexpected trailing bytes"));
+        }
+
+        Ok(ReducedTransaction {
+            bytes_to_sign,
+            unsigned_tx,
+            input_keys,
+        })
+    }
+
+    fn sign(&self, secrets: &[DlogProverInput]) -> Result<Transaction, JsValue> {
+        let secrets: HashMap<Vec<u8>, &DlogProverInput> = secrets
+            .iter()
+            .map(|s| (s.public_image().h.sigma_serialize_bytes(), s))
+            .collect();
+
+        let tx = &self.unsigned_tx.0;
+        let inputs = tx.inputs
+            .iter()
+            .zip(self.input_keys.iter())
+            .map(|(input, pk)| {
+                let secret = secrets
+                    .get(pk)
+                    .ok_or_else(|| JsValue::from_str("Missing secret key for an input"))?;
+                Ok(Input {
+                    box_id: input.box_id.clone(),
+                    spending_proof: ProverResult {
+                        proof: ProofBytes::Some(sign_message_with(secret, &self.bytes_to_sign)),
+                        extension: input.extension.clone(),
+                    },
+                })
+            })
+            .collect::<Result<Vec<Input>, JsValue>>()?;
+
+        Ok(Transaction(chain::transaction::Transaction::new(
+            inputs,
+            tx.data_inputs.clone(),
+            tx.output_candidates.clone(),
+        )))
+    }
+}
+
+/// Read unsigned VLQ encoded integer
+fn read_vlq(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+        shift += 7;
+        if shift >= 64 {
+            return None;
+        }
+    }
+}
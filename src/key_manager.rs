@@ -1,16 +1,81 @@
 use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
 use web_sys::console;
 
-const ABSOLUTE_MIN_GAP_LIMIT: i32 = 21;
+use std::collections::BTreeMap;
+
+use crate::wallet_file::{WalletFile, WalletSecret};
+use crate::password_crypto::{parse_optional_kdf, Kdf, ROUNDS};
 
 #[wasm_bindgen]
-pub struct KeyManager {}
+pub struct KeyManager {
+    name: String,
+    secret: WalletSecret,
+}
 
 #[wasm_bindgen]
 impl KeyManager {
-    pub fn recover(_mnemonic: &str) -> KeyManager {
+    pub fn recover(mnemonic: &str) -> KeyManager {
         console::log_1(&"Recovering KeyManager from mnemonic phrase".into());
 
-        return KeyManager {};
+        KeyManager {
+            name: String::new(),
+            secret: WalletSecret {
+                mnemonic: Some(mnemonic.to_string()),
+                seed: None,
+                accounts: vec![0],
+                labels: BTreeMap::new(),
+                network: "mainnet".to_string(),
+            },
+        }
+    }
+
+    /// Open encrypted wallet file (see `createWalletFile`)
+    pub fn open(wallet_file: &JsValue, password: &str) -> Result<KeyManager, JsValue> {
+        let file: WalletFile = wallet_file
+            .into_serde()
+            .map_err(|e| JsValue::from_str(&format!("Invalid wallet file: {}", e)))?;
+        let secret = file.open(password)?;
+
+        Ok(KeyManager {
+            name: file.name,
+            secret,
+        })
+    }
+
+    /// Encrypt the wallet into the file format accepted by `open`.
+    /// `kdf` is optional (see `passwordEncrypt`).
+    pub fn to_wallet_file(&self, name: &str, password: &str, kdf: &JsValue) -> Result<JsValue, JsValue> {
+        let kdf = parse_optional_kdf(kdf)?.unwrap_or(Kdf::Pbkdf2HmacSha512 { rounds: ROUNDS });
+        let file = WalletFile::seal(name, password, &self.secret, kdf)?;
+
+        JsValue::from_serde(&file).map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn network(&self) -> String {
+        self.secret.network.clone()
+    }
+
+    pub fn accounts(&self) -> Vec<u32> {
+        self.secret.accounts.clone()
+    }
+
+    /// Address labels as `{address: label}`
+    pub fn labels(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.secret.labels).map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+
+    pub fn set_label(&mut self, address: &str, label: &str) {
+        self.secret.labels.insert(address.to_string(), label.to_string());
+    }
+
+    pub fn add_account(&mut self, index: u32) {
+        if !self.secret.accounts.contains(&index) {
+            self.secret.accounts.push(index);
+        }
     }
 }
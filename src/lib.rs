@@ -1,27 +1,50 @@
+// JS arrays are passed to exported functions as boxed slices
+#![allow(clippy::boxed_local)]
+
 #[macro_use]
 extern crate serde_derive;
 
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 
 use hdpath::StandardHDPath;
 use wasm_bindgen::prelude::*;
-use web_sys::console;
 
 pub use address::*;
+pub use address_book::*;
+pub use ergo_box::*;
+pub use ergo_pay::*;
 pub use key_manager::*;
+pub use message_signing::*;
+pub use node_secret::*;
+pub use ownership_proof::*;
 pub use password_crypto::*;
+pub use password_strength::*;
+pub use payment_uri::*;
+pub use stealth::*;
 pub use transaction::*;
+pub use tx_summary::*;
+pub use wallet_file::*;
 
-use ergo_lib::wallet::secret_key::SecretKey;
-use ergo_lib::chain::Base16DecodedBytes;
-use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
 use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
 
+use message_signing::dlog_secret_from_hex;
+
 mod key_manager;
 mod address;
+mod address_book;
+mod ergo_box;
+mod ergo_pay;
+mod message_signing;
+mod node_secret;
+mod ownership_proof;
 mod password_crypto;
+mod password_strength;
+mod payment_uri;
+mod stealth;
 mod utils;
 mod transaction;
+mod tx_summary;
+mod wallet_file;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -29,7 +52,6 @@ mod transaction;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-const MINER_ERGO_TREE: &str = "1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304";
 const MINERS_FEE_MAINNET_ADDRESS: &str =
     "2iHkR7CWvD1R4j1yZg5bkeDRQavjAaVPeTDFGGLZduHyfWMuYpmhHocX8GJoaieTx78FntzJbCBVL6rf96ocJoZdmWBL2fci7NqWgAirppPQmZ7fN9V6z13Ay6brPriBKYqLp1bT2Fk4FkFLCfdPpe";
 
@@ -49,36 +71,12 @@ pub fn parse_hd_path(path: &str) -> Vec<u32> {
 
 #[wasm_bindgen(js_name = "publicFromSecret")]
 pub fn public_from_secret(secret: &str) -> Vec<u8> {
-    let scalar_bytes = Base16DecodedBytes::try_from(secret.to_string()).unwrap();
-    let bytes: &[u8; 32] = scalar_bytes.0.as_slice().try_into().unwrap();
-
-    DlogProverInput::from_bytes(bytes).unwrap().public_image().h.sigma_serialize_bytes()
+    dlog_secret_from_hex(secret).unwrap().public_image().h.sigma_serialize_bytes()
 }
 
 #[cfg(test)]
 mod tests {
-    use std::convert::TryFrom;
-
     use ergo_lib::chain::transaction::Transaction;
-    use ergo_lib::chain::ergo_box::ErgoBoxCandidate;
-    use ergo_lib::chain::ergo_box::BoxValue;
-    use ergo_lib::chain::ergo_box::NonMandatoryRegisters;
-    use ergo_lib::chain::Base16DecodedBytes;
-    use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
-    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
-
-    fn create_output(value: u64, ergo_tree: &str, height: u32) -> ErgoBoxCandidate {
-        let ergo_tree_bytes = Base16DecodedBytes::try_from(ergo_tree.to_string()).unwrap();
-        let tree = ErgoTree::sigma_parse_bytes(ergo_tree_bytes.0).unwrap();
-
-        ErgoBoxCandidate {
-            value: BoxValue::new(value).unwrap(),
-            ergo_tree: tree,
-            tokens: vec![],
-            additional_registers: NonMandatoryRegisters::empty(),
-            creation_height: height,
-        }
-    }
 
     #[test]
     pub fn tx_id_test() {
@@ -0,0 +1,121 @@
+use wasm_bindgen::prelude::*;
+
+use std::convert::TryInto;
+
+use generic_array::GenericArray;
+use k256::Scalar;
+use zeroize::Zeroize;
+
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+use ergo_lib::ergotree_ir::address::{AddressEncoder, NetworkPrefix};
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::{self, EcPoint};
+
+use crate::utils::{blake2b256, secret_from_hex};
+
+/// Size of the challenge in bytes (soundness of the sigma protocol, 192 bits)
+const CHALLENGE_SIZE: usize = 24;
+/// Size of the prover response (z) in bytes
+const RESPONSE_SIZE: usize = 32;
+pub const SIGNATURE_SIZE: usize = CHALLENGE_SIZE + RESPONSE_SIZE;
+
+/// Prefix of a proof tree leaf in the Fiat-Shamir serialization
+const FIAT_SHAMIR_LEAF_PREFIX: u8 = 1;
+
+/// Sign an arbitrary message with the secret key (hex encoded).
+///
+/// Produces a Schnorr signature over `proveDlog(pk)` in the same format as the
+/// Ergo node: `challenge (24 bytes) || z (32 bytes)`.
+#[wasm_bindgen(js_name = "signMessage")]
+pub fn sign_message(secret: &str, message: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let secret = dlog_secret_from_hex(secret)?;
+    Ok(sign_message_with(&secret, message))
+}
+
+/// Verify a signature produced by `signMessage` (or by the node) against
+/// the public key behind a P2PK address.
+#[wasm_bindgen(js_name = "verifySignature")]
+pub fn verify_signature(address: &str, message: &[u8], signature: &[u8]) -> Result<bool, JsValue> {
+    let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
+    let address = encoder
+        .parse_address_from_str(address)
+        .map_err(|e| JsValue::from_str(&format!("Invalid address: {:?}", e)))?;
+
+    match address {
+        ergo_lib::ergotree_ir::address::Address::P2PK(pd) => Ok(verify_message_with(&pd.h, message, signature)),
+        _ => Err(JsValue::from_str("Only P2PK addresses can verify message signatures")),
+    }
+}
+
+pub(crate) fn dlog_secret_from_hex(secret: &str) -> Result<DlogProverInput, JsValue> {
+    let scalar_bytes = secret_from_hex(secret)
+        .ok_or_else(|| JsValue::from_str("Secret key is not a valid hex string"))?;
+    let bytes: &[u8; 32] = scalar_bytes.as_slice()
+        .try_into()
+        .map_err(|_| JsValue::from_str("Secret key must be 32 bytes"))?;
+
+    DlogProverInput::from_bytes(bytes).ok_or_else(|| JsValue::from_str("Secret key is out of range"))
+}
+
+pub(crate) fn sign_message_with(secret: &DlogProverInput, message: &[u8]) -> Vec<u8> {
+    let pk = secret.public_image().h;
+
+    // commitment a = g^r
+    let mut r = DlogProverInput::random().w;
+    let commitment = dlog_group::exponentiate(&dlog_group::generator(), &r);
+
+    let challenge = fiat_shamir_challenge(&pk, &commitment, message);
+
+    // response z = r + e * w (mod n)
+    let z = r + &(challenge_to_scalar(&challenge) * &secret.w);
+    r.zeroize();
+
+    let mut signature = Vec::with_capacity(SIGNATURE_SIZE);
+    signature.extend_from_slice(&challenge);
+    signature.extend_from_slice(z.to_bytes().as_slice());
+    signature
+}
+
+pub(crate) fn verify_message_with(pk: &EcPoint, message: &[u8], signature: &[u8]) -> bool {
+    if signature.len() != SIGNATURE_SIZE {
+        return false;
+    }
+
+    let challenge = &signature[..CHALLENGE_SIZE];
+    let z = Scalar::from_bytes_reduced(GenericArray::from_slice(&signature[CHALLENGE_SIZE..]));
+
+    // recompute commitment a = g^z * h^(-e)
+    let e = challenge_to_scalar(challenge);
+    let commitment = dlog_group::exponentiate(&dlog_group::generator(), &z)
+        * &dlog_group::exponentiate(pk, &(-e));
+
+    fiat_shamir_challenge(pk, &commitment, message)[..] == challenge[..]
+}
+
+/// Fiat-Shamir challenge for a single `proveDlog` leaf:
+/// blake2b256(leaf bytes || message), truncated to the soundness size
+fn fiat_shamir_challenge(pk: &EcPoint, commitment: &EcPoint, message: &[u8]) -> [u8; CHALLENGE_SIZE] {
+    // proposition is serialized as ErgoTree with constant segregation
+    let mut prop_bytes = vec![0x10, 0x01, 0x08, 0xcd];
+    prop_bytes.extend_from_slice(&pk.sigma_serialize_bytes());
+    prop_bytes.extend_from_slice(&[0x73, 0x00]);
+
+    let commitment_bytes = commitment.sigma_serialize_bytes();
+
+    let mut bytes = vec![FIAT_SHAMIR_LEAF_PREFIX];
+    bytes.extend_from_slice(&(prop_bytes.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&prop_bytes);
+    bytes.extend_from_slice(&(commitment_bytes.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&commitment_bytes);
+    bytes.extend_from_slice(message);
+
+    let mut challenge = [0u8; CHALLENGE_SIZE];
+    challenge.copy_from_slice(&blake2b256(&bytes)[..CHALLENGE_SIZE]);
+    challenge
+}
+
+fn challenge_to_scalar(challenge: &[u8]) -> Scalar {
+    let mut bytes = [0u8; 32];
+    bytes[32 - challenge.len()..].copy_from_slice(challenge);
+    Scalar::from_bytes_reduced(GenericArray::from_slice(&bytes))
+}
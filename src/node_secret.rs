@@ -0,0 +1,171 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::convert::TryFrom;
+
+use hmac::Hmac;
+use pbkdf2::pbkdf2;
+use sha2::{Sha256, Sha512};
+use aes_gcm::{Aes256Gcm, AesGcm};
+use aes_gcm::aes::Aes256;
+use aes_gcm::aead::{Aead, NewAead, generic_array::GenericArray};
+use aes_gcm::aead::generic_array::typenum::U16;
+
+use ergo_lib::chain::{Base16DecodedBytes, Base16EncodedBytes};
+use js_sys::Uint8Array;
+use zeroize::Zeroizing;
+
+use crate::password_crypto::{PasswordCryptoError, MAX_PBKDF2_ROUNDS};
+use crate::utils::random_bytes;
+
+/// Defaults of the Ergo node `ergo.wallet.secretStorage.encryption` settings
+const NODE_PRF: &str = "HmacSHA256";
+const NODE_ROUNDS: u32 = 128_000;
+const NODE_KEY_BITS: u32 = 256;
+
+const SALT_SIZE: usize = 32;
+const IV_SIZE: usize = 16;
+const AUTH_TAG_SIZE: usize = 16;
+
+/// Parameters of the key derivation (PBKDF2)
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct NodeCipherParams {
+    pub prf: String,
+    pub c: u32,
+    #[serde(rename = "dkLen")]
+    pub dk_len: u32,
+}
+
+/// Encrypted secret as stored by the Ergo node (`secret.json`)
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct NodeEncryptedSecret {
+    #[serde(rename = "cipherText")]
+    pub cipher_text: String,
+    pub salt: String,
+    pub iv: String,
+    #[serde(rename = "authTag")]
+    pub auth_tag: String,
+    #[serde(rename = "cipherParams")]
+    pub cipher_params: NodeCipherParams,
+}
+
+/// Encrypt secret (seed) into the Ergo node secret storage JSON format,
+/// salt and IV are generated from the system CSPRNG
+#[wasm_bindgen(js_name = "exportNodeSecret")]
+pub fn export_node_secret(password: &str, data: &[u8]) -> Result<JsValue, JsValue> {
+    let salt = random_bytes(SALT_SIZE).map_err(|e| JsValue::from_str(&format!("{}", e)))?;
+    let iv = random_bytes(IV_SIZE).map_err(|e| JsValue::from_str(&format!("{}", e)))?;
+    export_node_secret_deterministic(password, &salt, &iv, data)
+}
+
+/// Encrypt secret (seed) into the Ergo node secret storage JSON format with explicit salt and IV.
+/// For deterministic test vectors only, use `exportNodeSecret` instead.
+#[wasm_bindgen(js_name = "exportNodeSecretDeterministic")]
+pub fn export_node_secret_deterministic(
+    password: &str,
+    salt: &[u8],
+    iv: &[u8],
+    data: &[u8],
+) -> Result<JsValue, JsValue> {
+    if salt.len() != SALT_SIZE {
+        return Err(JsValue::from_str(&format!("Invalid Salt Size, expected {} bytes", SALT_SIZE)));
+    }
+
+    let cipher_params = NodeCipherParams {
+        prf: NODE_PRF.to_string(),
+        c: NODE_ROUNDS,
+        dk_len: NODE_KEY_BITS,
+    };
+
+    let key = derive_key(&cipher_params, password, salt)?;
+    let mut encrypted = aes_gcm_encrypt(&key, iv, data)?;
+    let auth_tag = encrypted.split_off(encrypted.len() - AUTH_TAG_SIZE);
+
+    let secret = NodeEncryptedSecret {
+        cipher_text: Base16EncodedBytes::new(encrypted.as_slice()).into(),
+        salt: Base16EncodedBytes::new(salt).into(),
+        iv: Base16EncodedBytes::new(iv).into(),
+        auth_tag: Base16EncodedBytes::new(auth_tag.as_slice()).into(),
+        cipher_params,
+    };
+
+    JsValue::from_serde(&secret).map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+}
+
+/// Decrypt secret (seed) from the Ergo node secret storage JSON format.
+/// Returns `Uint8Array` the caller can clear (the plaintext is wiped from WASM memory before returning).
+#[wasm_bindgen(js_name = "importNodeSecret")]
+pub fn import_node_secret(password: &str, json: &JsValue) -> Result<Uint8Array, JsValue> {
+    let decrypted = decrypt_node_secret(password, json)?;
+
+    Ok(Uint8Array::from(decrypted.as_slice()))
+}
+
+fn decrypt_node_secret(password: &str, json: &JsValue) -> Result<Zeroizing<Vec<u8>>, JsValue> {
+    let secret: NodeEncryptedSecret = json
+        .into_serde()
+        .map_err(|e| JsValue::from_str(&format!("Invalid node secret: {}", e)))?;
+
+    let salt = decode_hex("salt", &secret.salt)?;
+    let iv = decode_hex("iv", &secret.iv)?;
+    let auth_tag = decode_hex("authTag", &secret.auth_tag)?;
+    if auth_tag.len() != AUTH_TAG_SIZE {
+        return Err(JsValue::from_str(&format!("Invalid authTag Size, expected {} bytes", AUTH_TAG_SIZE)));
+    }
+    let mut encrypted = decode_hex("cipherText", &secret.cipher_text)?;
+    encrypted.extend_from_slice(&auth_tag);
+
+    let key = derive_key(&secret.cipher_params, password, &salt)?;
+    aes_gcm_decrypt(&key, &iv, &encrypted)
+}
+
+fn derive_key(params: &NodeCipherParams, password: &str, salt: &[u8]) -> Result<Zeroizing<Vec<u8>>, JsValue> {
+    if salt.is_empty() {
+        return Err(JsValue::from_str("Salt is empty"));
+    }
+    if params.c == 0 || params.c > MAX_PBKDF2_ROUNDS {
+        return Err(PasswordCryptoError::InvalidParameters(format!(
+            "Unsupported number of PBKDF2 iterations: {}", params.c
+        )).into());
+    }
+    if params.dk_len != NODE_KEY_BITS {
+        return Err(JsValue::from_str(&format!("Unsupported key length: {} bits", params.dk_len)));
+    }
+
+    let mut key = Zeroizing::new(vec![0u8; (params.dk_len / 8) as usize]);
+    match params.prf.as_str() {
+        "HmacSHA256" => pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, params.c, &mut key),
+        "HmacSHA512" => pbkdf2::<Hmac<Sha512>>(password.as_bytes(), salt, params.c, &mut key),
+        prf => return Err(JsValue::from_str(&format!("Unsupported pseudo-random function: {}", prf))),
+    }
+    Ok(key)
+}
+
+// The node generates 16 bytes IV, 12 bytes is the standard one for GCM
+fn aes_gcm_encrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let key = GenericArray::from_slice(key);
+    let result = match iv.len() {
+        12 => Aes256Gcm::new(key).encrypt(GenericArray::from_slice(iv), data),
+        16 => AesGcm::<Aes256, U16>::new(key).encrypt(GenericArray::from_slice(iv), data),
+        len => return Err(JsValue::from_str(&format!("Invalid IV size: {} bytes", len))),
+    };
+    result.map_err(|err| JsValue::from_str(&format!("Cannot encrypt the data: {:?}", err)))
+}
+
+fn aes_gcm_decrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Zeroizing<Vec<u8>>, JsValue> {
+    let key = GenericArray::from_slice(key);
+    let result = match iv.len() {
+        12 => Aes256Gcm::new(key).decrypt(GenericArray::from_slice(iv), data),
+        16 => AesGcm::<Aes256, U16>::new(key).decrypt(GenericArray::from_slice(iv), data),
+        len => return Err(JsValue::from_str(&format!("Invalid IV size: {} bytes", len))),
+    };
+    result
+        .map(Zeroizing::new)
+        .map_err(|err| JsValue::from_str(&format!("Cannot decrypt the data: {:?}", err)))
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, JsValue> {
+    Base16DecodedBytes::try_from(value.to_string())
+        .map(|bytes| bytes.0)
+        .map_err(|_| JsValue::from_str(&format!("Field {} is not a valid hex string", field)))
+}
@@ -0,0 +1,117 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::convert::TryFrom;
+
+use ergo_lib::chain::{Base16DecodedBytes, Base16EncodedBytes};
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+
+use crate::address::Address;
+use crate::message_signing::{dlog_secret_from_hex, sign_message_with, verify_message_with};
+
+pub const OWNERSHIP_PROOF_TYPE: &str = "ergo-address-ownership";
+pub const OWNERSHIP_PROOF_VERSION: u32 = 1;
+/// Signature scheme of `signMessage`
+const SIGNATURE_ALGORITHM: &str = "schnorr-blake2b256";
+/// Tolerated difference between the clocks of the prover and the verifier
+const MAX_CLOCK_SKEW_MS: u64 = 5 * 60 * 1000;
+
+/// Signed statement that the owner of the address knows its secret key
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct OwnershipProof {
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    pub version: u32,
+    pub algorithm: String,
+    pub address: String,
+    /// Nonce given by the verifier
+    pub challenge: String,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    /// Signature of the statement (hex encoded)
+    pub signature: String,
+}
+
+impl OwnershipProof {
+    /// Statement covered by the signature
+    fn message(&self) -> Vec<u8> {
+        format!(
+            "{} v{}\naddress: {}\nchallenge: {}\ntimestamp: {}",
+            self.proof_type, self.version, self.address, self.challenge, self.timestamp
+        ).into_bytes()
+    }
+}
+
+/// Prove control of the P2PK address with its secret key (hex encoded).
+/// Returns JSON `{type, version, algorithm, address, challenge, timestamp, signature}`.
+#[wasm_bindgen(js_name = "proveAddressOwnership")]
+pub fn prove_address_ownership(secret: &str, address: &str, challenge: &str) -> Result<JsValue, JsValue> {
+    if challenge.is_empty() {
+        return Err(JsValue::from_str("Challenge must not be empty"));
+    }
+    let secret = dlog_secret_from_hex(secret)?;
+    let pk = secret.public_image().h.sigma_serialize_bytes();
+    if p2pk_public_key(address)? != pk {
+        return Err(JsValue::from_str("Secret key does not belong to the address"));
+    }
+
+    let mut proof = OwnershipProof {
+        proof_type: OWNERSHIP_PROOF_TYPE.to_string(),
+        version: OWNERSHIP_PROOF_VERSION,
+        algorithm: SIGNATURE_ALGORITHM.to_string(),
+        address: address.to_string(),
+        challenge: challenge.to_string(),
+        timestamp: js_sys::Date::now() as u64,
+        signature: String::new(),
+    };
+    let signature = sign_message_with(&secret, &proof.message());
+    proof.signature = Base16EncodedBytes::new(signature.as_slice()).into();
+
+    JsValue::from_serde(&proof).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
+
+/// Verify the proof for the challenge given to the prover.
+/// If `max_age_ms` is set, proofs older than that are rejected.
+#[wasm_bindgen(js_name = "verifyAddressOwnership")]
+pub fn verify_address_ownership(proof: &JsValue, challenge: &str, max_age_ms: Option<f64>) -> Result<bool, JsValue> {
+    let proof: OwnershipProof = proof
+        .into_serde()
+        .map_err(|e| JsValue::from_str(&format!("Invalid ownership proof: {}", e)))?;
+    Ok(verify_proof(&proof, challenge, js_sys::Date::now() as u64, max_age_ms.map(|a| a as u64)))
+}
+
+pub(crate) fn verify_proof(proof: &OwnershipProof, challenge: &str, now: u64, max_age_ms: Option<u64>) -> bool {
+    if proof.proof_type != OWNERSHIP_PROOF_TYPE
+        || proof.version != OWNERSHIP_PROOF_VERSION
+        || proof.algorithm != SIGNATURE_ALGORITHM
+        || challenge.is_empty()
+        || proof.challenge != challenge
+    {
+        return false;
+    }
+
+    if proof.timestamp > now + MAX_CLOCK_SKEW_MS {
+        return false;
+    }
+    if let Some(max_age) = max_age_ms {
+        if now.saturating_sub(proof.timestamp) > max_age {
+            return false;
+        }
+    }
+
+    let pk = match p2pk_public_key(&proof.address).ok().and_then(|pk| EcPoint::sigma_parse_bytes(pk).ok()) {
+        Some(pk) => pk,
+        None => return false,
+    };
+    match Base16DecodedBytes::try_from(proof.signature.clone()) {
+        Ok(signature) => verify_message_with(&pk, &proof.message(), &signature.0),
+        Err(_) => false,
+    }
+}
+
+fn p2pk_public_key(address: &str) -> Result<Vec<u8>, JsValue> {
+    Address::from_base58(address)?
+        .public_key()
+        .ok_or_else(|| JsValue::from_str("Only P2PK addresses can prove ownership"))
+}
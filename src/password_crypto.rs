@@ -1,72 +1,595 @@
 use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use js_sys::Uint8Array;
+
+use std::convert::TryInto;
+use std::fmt;
 
 use hmac::Hmac;
 use pbkdf2::pbkdf2;
 use sha2::{Sha512};
+use scrypt::{scrypt, ScryptParams};
+use argon2::{Algorithm, Argon2, Version};
 use aes_gcm::Aes256Gcm;
-use aes_gcm::aead::{Aead, NewAead, generic_array::GenericArray};
+use aes_gcm::aead::{Aead, NewAead, Payload, generic_array::GenericArray};
+use zeroize::Zeroizing;
+
+use crate::utils::random_bytes;
 
 const SALT_SIZE: usize = 32;
 const NONCE_SIZE: usize = 12;
 const KEY_SIZE: usize = 32;
-const ROUNDS: u32 = 10_000;
+const TAG_SIZE: usize = 16;
+pub(crate) const ROUNDS: u32 = 10_000;
+
+/// Magic bytes marking a versioned envelope ("Ergo Wallet Secret")
+const ENVELOPE_MAGIC: [u8; 4] = [0x45, 0x57, 0x53, 0x45];
+const ENVELOPE_VERSION: u8 = 1;
+
+/// Legacy (headerless) format: `salt || nonce || ciphertext`
+/// encrypted with PBKDF2-HMAC-SHA512 (10 000 rounds) and AES-256-GCM
+const LEGACY_ROUNDS: u32 = 10_000;
+
+const KDF_PBKDF2_HMAC_SHA512: u8 = 1;
+const KDF_SCRYPT: u8 = 2;
+const KDF_ARGON2ID: u8 = 3;
+const CIPHER_AES_256_GCM: u8 = 1;
+
+/// Upper bounds for the calibration and for parameters read from an envelope header,
+/// so that a corrupted or crafted envelope cannot exhaust memory or hang decryption
+pub(crate) const MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const MAX_SCRYPT_R: u32 = 32;
+const MAX_SCRYPT_P: u32 = 16;
+const MAX_ARGON2_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_ARGON2_ITERATIONS: u32 = 64;
+const MAX_ARGON2_PARALLELISM: u32 = 16;
+
+/// Lower bounds, so that a secret is never written with a trivial key derivation
+const MIN_PBKDF2_ROUNDS: u32 = 1_000;
+const MIN_SCRYPT_LOG_N: u8 = 10;
+const MIN_ARGON2_MEMORY_KIB: u32 = 1024;
+
+/// Reason why encryption or decryption failed.
+/// In JS it is thrown as `Error` whose `name` is the variant name.
+#[derive(PartialEq, Debug, Clone)]
+pub enum PasswordCryptoError {
+    /// Encrypted data is shorter than its header requires
+    TruncatedInput,
+    /// Envelope written by a newer version of the module
+    UnsupportedVersion(u8),
+    /// Unknown key derivation function or cipher
+    UnsupportedAlgorithm(String),
+    /// Invalid salt, nonce or key derivation parameters
+    InvalidParameters(String),
+    /// Wrong password or corrupted data
+    AuthenticationFailed,
+}
+
+impl PasswordCryptoError {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PasswordCryptoError::TruncatedInput => "TruncatedInput",
+            PasswordCryptoError::UnsupportedVersion(_) => "UnsupportedVersion",
+            PasswordCryptoError::UnsupportedAlgorithm(_) => "UnsupportedAlgorithm",
+            PasswordCryptoError::InvalidParameters(_) => "InvalidParameters",
+            PasswordCryptoError::AuthenticationFailed => "AuthenticationFailed",
+        }
+    }
+}
+
+impl fmt::Display for PasswordCryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordCryptoError::TruncatedInput => write!(f, "Encrypted data is truncated"),
+            PasswordCryptoError::UnsupportedVersion(v) => write!(f, "Unsupported envelope version: {}", v),
+            PasswordCryptoError::UnsupportedAlgorithm(a) => write!(f, "Unsupported algorithm: {}", a),
+            PasswordCryptoError::InvalidParameters(msg) => write!(f, "{}", msg),
+            PasswordCryptoError::AuthenticationFailed =>
+                write!(f, "Cannot decrypt the data: wrong password or corrupted data"),
+        }
+    }
+}
+
+impl From<PasswordCryptoError> for JsValue {
+    fn from(e: PasswordCryptoError) -> Self {
+        let error = js_sys::Error::new(&e.to_string());
+        error.set_name(e.name());
+        error.into()
+    }
+}
+
+/// Key derivation function with its parameters.
+/// In JS it is `{kdf: "pbkdf2-sha512", rounds}`, `{kdf: "scrypt", logN, r, p}`
+/// or `{kdf: "argon2id", memoryKib, iterations, parallelism}`
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kdf")]
+pub(crate) enum Kdf {
+    #[serde(rename = "pbkdf2-sha512")]
+    Pbkdf2HmacSha512 { rounds: u32 },
+    #[serde(rename = "scrypt")]
+    Scrypt {
+        #[serde(rename = "logN")]
+        log_n: u8,
+        r: u32,
+        p: u32,
+    },
+    #[serde(rename = "argon2id")]
+    Argon2id {
+        #[serde(rename = "memoryKib")]
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl Kdf {
+    fn derive_key(&self, password: &str, salt: &[u8]) -> Result<Zeroizing<Vec<u8>>, PasswordCryptoError> {
+        let mut key = Zeroizing::new(vec![0u8; KEY_SIZE]);
+        match self {
+            Kdf::Pbkdf2HmacSha512 { rounds } =>
+                pbkdf2::<Hmac<Sha512>>(password.as_bytes(), salt, *rounds, &mut key),
+            Kdf::Scrypt { log_n, r, p } => {
+                let params = ScryptParams::new(*log_n, *r, *p)
+                    .map_err(|e| PasswordCryptoError::InvalidParameters(format!("Invalid scrypt parameters: {:?}", e)))?;
+                scrypt(password.as_bytes(), salt, &params, &mut key)
+                    .map_err(|e| PasswordCryptoError::InvalidParameters(format!("{:?}", e)))?;
+            }
+            Kdf::Argon2id { memory_kib, iterations, parallelism } => {
+                let argon2 = Argon2::new(None, *iterations, *memory_kib, *parallelism, Version::V0x13)
+                    .map_err(|e| PasswordCryptoError::InvalidParameters(format!("Invalid Argon2 parameters: {:?}", e)))?;
+                argon2.hash_password_into(Algorithm::Argon2id, password.as_bytes(), salt, &[], &mut key)
+                    .map_err(|e| PasswordCryptoError::InvalidParameters(format!("{:?}", e)))?;
+            }
+        }
+        Ok(key)
+    }
+
+    fn write(&self, output: &mut Vec<u8>) {
+        match self {
+            Kdf::Pbkdf2HmacSha512 { rounds } => {
+                output.push(KDF_PBKDF2_HMAC_SHA512);
+                output.extend_from_slice(&rounds.to_be_bytes());
+            }
+            Kdf::Scrypt { log_n, r, p } => {
+                output.push(KDF_SCRYPT);
+                output.push(*log_n);
+                output.extend_from_slice(&r.to_be_bytes());
+                output.extend_from_slice(&p.to_be_bytes());
+            }
+            Kdf::Argon2id { memory_kib, iterations, parallelism } => {
+                output.push(KDF_ARGON2ID);
+                output.extend_from_slice(&memory_kib.to_be_bytes());
+                output.extend_from_slice(&iterations.to_be_bytes());
+                output.extend_from_slice(&parallelism.to_be_bytes());
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Kdf, PasswordCryptoError> {
+        let kdf = match reader.read_u8()? {
+            KDF_PBKDF2_HMAC_SHA512 => Kdf::Pbkdf2HmacSha512 { rounds: reader.read_u32()? },
+            KDF_SCRYPT => Kdf::Scrypt {
+                log_n: reader.read_u8()?,
+                r: reader.read_u32()?,
+                p: reader.read_u32()?,
+            },
+            KDF_ARGON2ID => Kdf::Argon2id {
+                memory_kib: reader.read_u32()?,
+                iterations: reader.read_u32()?,
+                parallelism: reader.read_u32()?,
+            },
+            id => return Err(PasswordCryptoError::UnsupportedAlgorithm(format!("key derivation function {}", id))),
+        };
+        kdf.check_bounds()?;
+        Ok(kdf)
+    }
+
+    /// Reject parameters outside of the supported cost range
+    fn check_bounds(&self) -> Result<(), PasswordCryptoError> {
+        let within_bounds = match self {
+            Kdf::Pbkdf2HmacSha512 { rounds } => (MIN_PBKDF2_ROUNDS..=MAX_PBKDF2_ROUNDS).contains(rounds),
+            Kdf::Scrypt { log_n, r, p } => (MIN_SCRYPT_LOG_N..=MAX_SCRYPT_LOG_N).contains(log_n)
+                && (1..=MAX_SCRYPT_R).contains(r)
+                && (1..=MAX_SCRYPT_P).contains(p),
+            Kdf::Argon2id { memory_kib, iterations, parallelism } =>
+                (MIN_ARGON2_MEMORY_KIB..=MAX_ARGON2_MEMORY_KIB).contains(memory_kib)
+                    && (1..=MAX_ARGON2_ITERATIONS).contains(iterations)
+                    && (1..=MAX_ARGON2_PARALLELISM).contains(parallelism),
+        };
+        if !within_bounds {
+            return Err(PasswordCryptoError::InvalidParameters(format!(
+                "Key derivation parameters are outside of the supported range: {:?}", self
+            )));
+        }
+        Ok(())
+    }
+
+    /// Same function with the cost doubled, up to the supported maximum
+    fn harder(&self) -> Kdf {
+        match self {
+            Kdf::Pbkdf2HmacSha512 { rounds } => Kdf::Pbkdf2HmacSha512 {
+                rounds: rounds.saturating_mul(2).min(MAX_PBKDF2_ROUNDS),
+            },
+            Kdf::Scrypt { log_n, r, p } => Kdf::Scrypt {
+                log_n: log_n.saturating_add(1).min(MAX_SCRYPT_LOG_N),
+                r: *r,
+                p: *p,
+            },
+            Kdf::Argon2id { memory_kib, iterations, parallelism } => Kdf::Argon2id {
+                memory_kib: memory_kib.saturating_mul(2).min(MAX_ARGON2_MEMORY_KIB),
+                iterations: *iterations,
+                parallelism: *parallelism,
+            },
+        }
+    }
+
+    /// Whether the cost reached the upper bound we allow to calibrate to
+    fn at_max_cost(&self) -> bool {
+        match self {
+            Kdf::Pbkdf2HmacSha512 { rounds } => *rounds >= MAX_PBKDF2_ROUNDS,
+            Kdf::Scrypt { log_n, .. } => *log_n >= MAX_SCRYPT_LOG_N,
+            Kdf::Argon2id { memory_kib, .. } => *memory_kib >= MAX_ARGON2_MEMORY_KIB,
+        }
+    }
+}
+
+/// Symmetric cipher used for the payload
+#[derive(PartialEq, Debug, Clone)]
+enum Cipher {
+    Aes256Gcm,
+}
+
+impl Cipher {
+    fn encrypt(&self, key: &[u8], nonce: &[u8], data: &[u8], aad: &[u8]) -> Result<Vec<u8>, PasswordCryptoError> {
+        self.check_sizes(key, nonce)?;
+        match self {
+            Cipher::Aes256Gcm => {
+                let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+                cipher.encrypt(GenericArray::from_slice(nonce), Payload { msg: data, aad })
+                    .map_err(|err| PasswordCryptoError::InvalidParameters(format!("Cannot encrypt the data: {:?}", err)))
+            }
+        }
+    }
+
+    fn decrypt(&self, key: &[u8], nonce: &[u8], data: &[u8], aad: &[u8]) -> Result<Zeroizing<Vec<u8>>, PasswordCryptoError> {
+        self.check_sizes(key, nonce)?;
+        match self {
+            Cipher::Aes256Gcm => {
+                let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+                cipher.decrypt(GenericArray::from_slice(nonce), Payload { msg: data, aad })
+                    .map(Zeroizing::new)
+                    .map_err(|_| PasswordCryptoError::AuthenticationFailed)
+            }
+        }
+    }
 
+    fn check_sizes(&self, key: &[u8], nonce: &[u8]) -> Result<(), PasswordCryptoError> {
+        let (key_size, nonce_size) = match self {
+            Cipher::Aes256Gcm => (KEY_SIZE, NONCE_SIZE),
+        };
+        if key.len() != key_size {
+            return Err(PasswordCryptoError::InvalidParameters(format!("Invalid Key Size, expected {} bytes", key_size)));
+        }
+        if nonce.len() != nonce_size {
+            return Err(PasswordCryptoError::InvalidParameters(format!("Invalid Nonce Size, expected {} bytes", nonce_size)));
+        }
+        Ok(())
+    }
+
+    fn id(&self) -> u8 {
+        match self {
+            Cipher::Aes256Gcm => CIPHER_AES_256_GCM,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Cipher, PasswordCryptoError> {
+        match id {
+            CIPHER_AES_256_GCM => Ok(Cipher::Aes256Gcm),
+            _ => Err(PasswordCryptoError::UnsupportedAlgorithm(format!("cipher {}", id))),
+        }
+    }
+}
+
+/// Encrypted secret together with everything needed to decrypt it:
+/// `magic || version || kdf || kdf params || cipher || salt || nonce || ciphertext`
+/// (salt and nonce are prefixed by their length)
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct Envelope {
+    kdf: Kdf,
+    cipher: Cipher,
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl Envelope {
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.extend_from_slice(&ENVELOPE_MAGIC);
+        output.push(ENVELOPE_VERSION);
+        self.kdf.write(&mut output);
+        output.push(self.cipher.id());
+        output.push(self.salt.len() as u8);
+        output.extend_from_slice(&self.salt);
+        output.push(self.nonce.len() as u8);
+        output.extend_from_slice(&self.nonce);
+        output.extend_from_slice(&self.ciphertext);
+        output
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Envelope, PasswordCryptoError> {
+        if !bytes.starts_with(&ENVELOPE_MAGIC) {
+            return Self::from_legacy_bytes(bytes);
+        }
+
+        let mut reader = Reader::new(&bytes[ENVELOPE_MAGIC.len()..]);
+        let version = reader.read_u8()?;
+        if version != ENVELOPE_VERSION {
+            return Err(PasswordCryptoError::UnsupportedVersion(version));
+        }
+
+        let kdf = Kdf::read(&mut reader)?;
+        let cipher = Cipher::from_id(reader.read_u8()?)?;
+        let salt_len = reader.read_u8()? as usize;
+        let salt = reader.read_bytes(salt_len)?.to_vec();
+        let nonce_len = reader.read_u8()? as usize;
+        let nonce = reader.read_bytes(nonce_len)?.to_vec();
+
+        Ok(Envelope {
+            kdf,
+            cipher,
+            salt,
+            nonce,
+            ciphertext: reader.ciphertext()?,
+        })
+    }
+
+    fn from_legacy_bytes(bytes: &[u8]) -> Result<Envelope, PasswordCryptoError> {
+        let mut reader = Reader::new(bytes);
+        let salt = reader.read_bytes(SALT_SIZE)?.to_vec();
+        let nonce = reader.read_bytes(NONCE_SIZE)?.to_vec();
+
+        Ok(Envelope {
+            kdf: Kdf::Pbkdf2HmacSha512 { rounds: LEGACY_ROUNDS },
+            cipher: Cipher::Aes256Gcm,
+            salt,
+            nonce,
+            ciphertext: reader.ciphertext()?,
+        })
+    }
+}
+
+/// Minimal cursor over the envelope bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0 }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], PasswordCryptoError> {
+        if self.bytes.len() - self.pos < len {
+            return Err(PasswordCryptoError::TruncatedInput);
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, PasswordCryptoError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, PasswordCryptoError> {
+        Ok(u32::from_be_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Remaining bytes, which must at least hold the authentication tag
+    fn ciphertext(&self) -> Result<Vec<u8>, PasswordCryptoError> {
+        let rest = &self.bytes[self.pos..];
+        if rest.len() < TAG_SIZE {
+            return Err(PasswordCryptoError::TruncatedInput);
+        }
+        Ok(rest.to_vec())
+    }
+}
+
+/// Encrypt data with the password. Salt and nonce are generated internally
+/// from the system CSPRNG, so they are never reused.
+///
+/// `kdf` is optional, PBKDF2 is used when omitted. Other key derivation functions
+/// are selected with e.g. `{kdf: "argon2id", memoryKib: 65536, iterations: 3, parallelism: 1}`.
 #[wasm_bindgen(js_name = "passwordEncrypt")]
-pub fn password_encrypt(
+pub fn password_encrypt(password: &str, data: &[u8], kdf: &JsValue) -> Result<JsValue, JsValue> {
+    let kdf = parse_optional_kdf(kdf)?.unwrap_or(Kdf::Pbkdf2HmacSha512 { rounds: ROUNDS });
+    let envelope = encrypt_envelope_random(password, kdf, data, &[])?;
+
+    JsValue::from_serde(&envelope.to_bytes()).map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+}
+
+/// Encrypt data with explicit salt and nonce, `kdf` is optional (see `passwordEncrypt`).
+///
+/// For deterministic test vectors only: reusing a nonce with the same
+/// password breaks AES-GCM, use `passwordEncrypt` instead.
+#[wasm_bindgen(js_name = "passwordEncryptDeterministic")]
+pub fn password_encrypt_deterministic(
     password: &str,
     salt: &[u8],
     nonce: &[u8],
     data: &[u8],
+    kdf: &JsValue,
 ) -> Result<JsValue, JsValue> {
-    if salt.len() != SALT_SIZE {
-        return Err(JsValue::from(&format!("Invalid Salt Size, expected {} bytes", SALT_SIZE)));
-    }
+    let kdf = parse_optional_kdf(kdf)?.unwrap_or(Kdf::Pbkdf2HmacSha512 { rounds: ROUNDS });
+    let envelope = encrypt_envelope(password, kdf, salt, nonce, data, &[])?;
 
-    // Derive key
-    let mut key = vec![0u8; KEY_SIZE];
-    pbkdf2::<Hmac<Sha512>>(password.as_bytes(), salt, ROUNDS, &mut key);
+    JsValue::from_serde(&envelope.to_bytes()).map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+}
 
-    // Encrypt by AES
-    let cipher = Aes256Gcm::new(GenericArray::from_slice(&key[..]));
-    let result = cipher.encrypt(
-        GenericArray::from_slice(&nonce[..]),
-        data,
-    );
+/// Decrypt data produced by `passwordEncrypt`.
+/// Both versioned envelopes and the legacy headerless format are accepted.
+///
+/// The plaintext is wiped from WASM memory before returning, the caller
+/// should clear the returned array (`fill(0)`) as soon as it is not needed.
+///
+/// Throws `Error` named `TruncatedInput`, `UnsupportedVersion`, `UnsupportedAlgorithm`,
+/// `InvalidParameters` or `AuthenticationFailed` (wrong password or corrupted data).
+#[wasm_bindgen(js_name = "passwordDecrypt")]
+pub fn password_decrypt(password: &str, encrypted_data: &[u8]) -> Result<Uint8Array, JsValue> {
+    let (_, decrypted) = decrypt_envelope(password, encrypted_data, &[])?;
+
+    Ok(Uint8Array::from(decrypted.as_slice()))
+}
 
-    match result {
-        Ok(encrypted) => {
-            let mut output = vec![];
-            output.extend_from_slice(&salt);
-            output.extend_from_slice(&nonce);
-            output.extend_from_slice(&encrypted);
+/// Same as `passwordEncrypt`, additionally authenticating `aad`
+/// (e.g. wallet id or file header). The associated data is not stored,
+/// the exact same bytes must be given to `passwordDecryptWithAad`.
+#[wasm_bindgen(js_name = "passwordEncryptWithAad")]
+pub fn password_encrypt_with_aad(
+    password: &str,
+    data: &[u8],
+    aad: &[u8],
+    kdf: &JsValue,
+) -> Result<JsValue, JsValue> {
+    let kdf = parse_optional_kdf(kdf)?.unwrap_or(Kdf::Pbkdf2HmacSha512 { rounds: ROUNDS });
+    let envelope = encrypt_envelope_random(password, kdf, data, aad)?;
+
+    JsValue::from_serde(&envelope.to_bytes()).map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+}
+
+/// Decrypt data produced by `passwordEncryptWithAad`.
+/// Different associated data fails with `AuthenticationFailed`.
+/// The plaintext is returned as in `passwordDecrypt`.
+#[wasm_bindgen(js_name = "passwordDecryptWithAad")]
+pub fn password_decrypt_with_aad(password: &str, encrypted_data: &[u8], aad: &[u8]) -> Result<Uint8Array, JsValue> {
+    let (_, decrypted) = decrypt_envelope(password, encrypted_data, aad)?;
+
+    Ok(Uint8Array::from(decrypted.as_slice()))
+}
+
+/// Change the password of encrypted data without exposing the plaintext to JS.
+///
+/// Data is re-encrypted with fresh salt and nonce. `kdf` is optional
+/// (see `passwordEncrypt`), the current key derivation is kept when omitted.
+/// Optional `aad` must be the associated data the secret was encrypted with, it stays bound.
+#[wasm_bindgen(js_name = "passwordChange")]
+pub fn password_change(
+    encrypted_data: &[u8],
+    old_password: &str,
+    new_password: &str,
+    kdf: &JsValue,
+    aad: Option<Vec<u8>>,
+) -> Result<JsValue, JsValue> {
+    let aad = aad.unwrap_or_default();
+    let new_kdf = parse_optional_kdf(kdf)?;
+    let (old_kdf, decrypted) = decrypt_envelope(old_password, encrypted_data, &aad)?;
 
-            JsValue::from_serde(&output).map_err(|e| JsValue::from_str(&format!("{:?}", e)))
-        },
-        Err(err) => Err(JsValue::from_str(&format!("Cannot encrypt the data: {:?}", err)))
+    let envelope = encrypt_envelope_random(new_password, new_kdf.unwrap_or(old_kdf), &decrypted, &aad)?;
+
+    JsValue::from_serde(&envelope.to_bytes()).map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+}
+
+/// Find parameters of the key derivation function (`pbkdf2-sha512`, `scrypt`
+/// or `argon2id`) for which one derivation takes at least `target_ms` on this machine.
+/// The cost is doubled starting from the minimal recommended one.
+#[wasm_bindgen(js_name = "calibrateKdf")]
+pub fn calibrate_kdf(kdf: &str, target_ms: f64) -> Result<JsValue, JsValue> {
+    let mut params = match kdf {
+        "pbkdf2-sha512" => Kdf::Pbkdf2HmacSha512 { rounds: ROUNDS },
+        "scrypt" => Kdf::Scrypt { log_n: 14, r: 8, p: 1 },
+        "argon2id" => Kdf::Argon2id { memory_kib: 16 * 1024, iterations: 3, parallelism: 1 },
+        _ => return Err(JsValue::from_str(&format!("Unknown key derivation function: {}", kdf))),
+    };
+
+    let salt = [0u8; SALT_SIZE];
+    loop {
+        let started = js_sys::Date::now();
+        params.derive_key("calibration", &salt)?;
+        let elapsed = js_sys::Date::now() - started;
+
+        if elapsed >= target_ms || params.at_max_cost() {
+            break;
+        }
+        params = params.harder();
     }
+
+    JsValue::from_serde(&params).map_err(|e| JsValue::from_str(&format!("{:?}", e)))
 }
 
-#[wasm_bindgen(js_name = "passwordDecrypt")]
-pub fn password_decrypt(password: &str, encrypted_data: &[u8]) -> Result<JsValue, JsValue> {
+/// Decrypt envelope (or legacy data), returning the key derivation used with the plaintext
+pub(crate) fn decrypt_envelope(
+    password: &str,
+    encrypted_data: &[u8],
+    aad: &[u8],
+) -> Result<(Kdf, Zeroizing<Vec<u8>>), PasswordCryptoError> {
     // Extract meta information
-    let salt = &encrypted_data[0..SALT_SIZE];
-    let nonce = &encrypted_data[SALT_SIZE..(SALT_SIZE + NONCE_SIZE)];
-    let encrypted = &encrypted_data[(SALT_SIZE + NONCE_SIZE)..];
+    let envelope = Envelope::from_bytes(encrypted_data)?;
 
     // Derive key
-    let mut key = vec![0u8; KEY_SIZE];
-    pbkdf2::<Hmac<Sha512>>(password.as_bytes(), salt, ROUNDS, &mut key);
+    let key = envelope.kdf.derive_key(password, &envelope.salt)?;
 
     // Decrypt by AES
-    let cipher = Aes256Gcm::new(GenericArray::from_slice(&key[..]));
-    let result = cipher.decrypt(
-        GenericArray::from_slice(&nonce[..]),
-        encrypted
-    );
+    let decrypted = envelope.cipher.decrypt(&key, &envelope.nonce, &envelope.ciphertext, aad)?;
+
+    Ok((envelope.kdf, decrypted))
+}
+
+pub(crate) fn encrypt_envelope_random(
+    password: &str,
+    kdf: Kdf,
+    data: &[u8],
+    aad: &[u8],
+) -> Result<Envelope, JsValue> {
+    let salt = random_bytes(SALT_SIZE).map_err(|e| JsValue::from_str(&format!("{}", e)))?;
+    let nonce = random_bytes(NONCE_SIZE).map_err(|e| JsValue::from_str(&format!("{}", e)))?;
+
+    encrypt_envelope(password, kdf, &salt, &nonce, data, aad).map_err(JsValue::from)
+}
 
-    match result {
-        Ok(decrypted) =>
-            JsValue::from_serde(&decrypted).map_err(|e| JsValue::from_str(&format!("{:?}", e))),
-        Err(err) => Err(JsValue::from_str(&format!("Cannot encrypt the data: {:?}", err)))
+pub(crate) fn parse_optional_kdf(kdf: &JsValue) -> Result<Option<Kdf>, PasswordCryptoError> {
+    if kdf.is_undefined() || kdf.is_null() {
+        return Ok(None);
     }
+    let kdf: Kdf = kdf
+        .into_serde()
+        .map_err(|e| PasswordCryptoError::InvalidParameters(format!("Invalid key derivation parameters: {}", e)))?;
+    kdf.check_bounds()?;
+    Ok(Some(kdf))
+}
+
+fn encrypt_envelope(
+    password: &str,
+    kdf: Kdf,
+    salt: &[u8],
+    nonce: &[u8],
+    data: &[u8],
+    aad: &[u8],
+) -> Result<Envelope, PasswordCryptoError> {
+    if salt.len() != SALT_SIZE {
+        return Err(PasswordCryptoError::InvalidParameters(format!("Invalid Salt Size, expected {} bytes", SALT_SIZE)));
+    }
+    if nonce.len() != NONCE_SIZE {
+        return Err(PasswordCryptoError::InvalidParameters(format!("Invalid Nonce Size, expected {} bytes", NONCE_SIZE)));
+    }
+    // never write an envelope which `decrypt_envelope` would refuse to read
+    kdf.check_bounds()?;
+
+    let cipher = Cipher::Aes256Gcm;
+
+    // Derive key
+    let key = kdf.derive_key(password, salt)?;
+
+    // Encrypt by AES
+    let ciphertext = cipher.encrypt(&key, nonce, data, aad)?;
+
+    Ok(Envelope {
+        kdf,
+        cipher,
+        salt: salt.to_vec(),
+        nonce: nonce.to_vec(),
+        ciphertext,
+    })
 }
@@ -0,0 +1,191 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::collections::HashSet;
+
+/// Common passwords and words, one per line (lowercase)
+const WORDLIST: &str = include_str!("wordlist/common_passwords.txt");
+
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+const MIN_PATTERN_LENGTH: usize = 3;
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Entropy (bits) thresholds for scores 1..4
+const SCORE_THRESHOLDS: [f64; 4] = [28.0, 36.0, 60.0, 80.0];
+
+/// Result of the password strength estimation
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct PasswordStrength {
+    /// Estimated entropy in bits
+    pub entropy: f64,
+    /// 0 (very weak) to 4 (very strong)
+    pub score: u8,
+    /// `too_short`, `dictionary_word`, `repeated_characters`, `sequence` or `keyboard_pattern`
+    pub warnings: Vec<String>,
+}
+
+/// Estimate password strength: entropy, score and warnings about weak patterns
+#[wasm_bindgen(js_name = "passwordStrength")]
+pub fn password_strength(password: &str) -> Result<JsValue, JsValue> {
+    JsValue::from_serde(&estimate_strength(password)).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
+
+pub fn estimate_strength(password: &str) -> PasswordStrength {
+    let chars: Vec<char> = password.chars().collect();
+    let lowercase: Vec<char> = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let normalized: Vec<char> = lowercase.iter().map(|c| unleet(*c)).collect();
+    let bits_per_char = (charset_size(&chars) as f64).log2();
+
+    let mut warnings: Vec<String> = vec![];
+    let mut covered = vec![false; chars.len()];
+    let mut pattern_bits = 0.0;
+
+    if chars.len() < MIN_PASSWORD_LENGTH {
+        warnings.push("too_short".to_string());
+    }
+
+    // dictionary words, longest match first
+    let dictionary: HashSet<String> = WORDLIST
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| l.chars().map(unleet).collect())
+        .collect();
+    let dictionary_bits = (dictionary.len() as f64).log2() + 1.0;
+    let mut i = 0;
+    while i < normalized.len() {
+        let found = (i + MIN_PATTERN_LENGTH..=normalized.len())
+            .rev()
+            .find(|&j| dictionary.contains(&normalized[i..j].iter().collect::<String>()));
+        match found {
+            Some(j) => {
+                pattern_bits += dictionary_bits;
+                covered[i..j].iter_mut().for_each(|c| *c = true);
+                push_warning(&mut warnings, "dictionary_word");
+                i = j;
+            }
+            None => i += 1,
+        }
+    }
+
+    // repeats, sequences and keyboard walks
+    let mut start = 0;
+    while start < chars.len() {
+        let (len, kind) = pattern_at(&lowercase, start);
+        if len >= MIN_PATTERN_LENGTH && covered[start..start + len].iter().any(|c| !c) {
+            pattern_bits += bits_per_char + (len as f64).log2();
+            covered[start..start + len].iter_mut().for_each(|c| *c = true);
+            push_warning(&mut warnings, kind);
+            start += len;
+        } else {
+            start += 1;
+        }
+    }
+
+    let uncovered = covered.iter().filter(|c| !**c).count();
+    let entropy = pattern_bits + uncovered as f64 * bits_per_char;
+    let score = SCORE_THRESHOLDS.iter().filter(|t| entropy >= **t).count() as u8;
+
+    PasswordStrength {
+        entropy,
+        score,
+        warnings,
+    }
+}
+
+/// Whether `b` continues a pattern after `a`
+type PatternStep = fn(char, char) -> bool;
+
+/// Longest run starting at `start` of a single kind: repeated character,
+/// alphabet/digit sequence (forward or backward) or walk over a keyboard row
+fn pattern_at(chars: &[char], start: usize) -> (usize, &'static str) {
+    let kinds: [(&'static str, PatternStep); 4] = [
+        ("repeated_characters", |a, b| a == b),
+        ("sequence", |a, b| (b as i32 - a as i32) == 1),
+        ("sequence", |a, b| (a as i32 - b as i32) == 1),
+        ("keyboard_pattern", keyboard_adjacent),
+    ];
+
+    kinds
+        .iter()
+        .map(|(kind, step)| {
+            let len = 1 + chars[start..].windows(2).take_while(|w| step(w[0], w[1])).count();
+            (len, *kind)
+        })
+        .max_by_key(|(len, _)| *len)
+        .unwrap()
+}
+
+fn keyboard_adjacent(a: char, b: char) -> bool {
+    KEYBOARD_ROWS.iter().any(|row| {
+        let row: Vec<char> = row.chars().collect();
+        row.windows(2).any(|w| (w[0] == a && w[1] == b) || (w[0] == b && w[1] == a))
+    })
+}
+
+fn charset_size(chars: &[char]) -> u32 {
+    let mut size = 0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        size += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        size += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        size += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        size += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        size += 100;
+    }
+    size.max(1)
+}
+
+/// Undo common character substitutions ("p@ssw0rd")
+fn unleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        _ => c,
+    }
+}
+
+fn push_warning(warnings: &mut Vec<String>, warning: &str) {
+    if !warnings.iter().any(|w| w == warning) {
+        warnings.push(warning.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::estimate_strength;
+
+    #[test]
+    pub fn weak_passwords() {
+        let strength = estimate_strength("P@ssw0rd");
+        assert_eq!(strength.score, 0);
+        assert!(strength.warnings.contains(&"dictionary_word".to_string()));
+
+        let strength = estimate_strength("qwertyuiop123");
+        assert!(strength.score <= 1);
+        assert!(strength.warnings.contains(&"keyboard_pattern".to_string()));
+
+        let strength = estimate_strength("aaaaaaaaaaaa");
+        assert_eq!(strength.score, 0);
+        assert_eq!(strength.warnings, vec!["repeated_characters".to_string()]);
+    }
+
+    #[test]
+    pub fn strong_password() {
+        let strength = estimate_strength("vT9#kq2!Lx7$Rm4&Zp");
+        assert_eq!(strength.score, 4);
+        assert!(strength.warnings.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::collections::BTreeMap;
+
+use crate::address::{check_address, Network};
+use crate::transaction::{registers_from_hex, AssetValue, TxOutput};
+
+const URI_SCHEME: &str = "ergo:";
+const TOKEN_PARAM_PREFIX: &str = "token-";
+const TOKEN_ID_SIZE: usize = 32;
+
+/// Number of decimals of ERG (1 ERG = 10^9 nanoERG)
+const ERG_DECIMALS: usize = 9;
+const NANOERGS_IN_ERG: u64 = 1_000_000_000;
+
+/// Parse payment request URI `ergo:<address>?amount=<ERG>&token-<id>=<amount>&r4=<hex constant>`.
+/// Returns `TxOutput` (value in nanoERG) ready for `Transaction::create`.
+/// If the URI has no amount, value is "0" and should be filled by the user.
+#[wasm_bindgen(js_name = "parsePaymentUri")]
+pub fn parse_payment_uri(uri: &str, network: Network) -> Result<JsValue, JsValue> {
+    let output = payment_uri_to_output(uri, network).map_err(|e| JsValue::from_str(&e))?;
+    JsValue::from_serde(&output).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
+
+/// Generate payment request URI from `TxOutput` JSON (value in nanoERG)
+#[wasm_bindgen(js_name = "createPaymentUri")]
+pub fn create_payment_uri(output: &JsValue, network: Network) -> Result<String, JsValue> {
+    let output: TxOutput = output
+        .into_serde()
+        .map_err(|e| JsValue::from_str(&format!("Invalid output: {}", e)))?;
+    output_to_payment_uri(&output, network).map_err(|e| JsValue::from_str(&e))
+}
+
+pub(crate) fn payment_uri_to_output(uri: &str, network: Network) -> Result<TxOutput, String> {
+    let rest = match uri.get(..URI_SCHEME.len()) {
+        Some(scheme) if scheme.eq_ignore_ascii_case(URI_SCHEME) => &uri[URI_SCHEME.len()..],
+        _ => return Err(format!("Payment URI must start with '{}'", URI_SCHEME)),
+    };
+    let (address, query) = match rest.find('?') {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, ""),
+    };
+    check_address(address, network).map_err(|e| format!("Invalid address: {}", e))?;
+
+    let mut value: Option<String> = None;
+    let mut assets: Vec<AssetValue> = vec![];
+    let mut registers: BTreeMap<String, String> = BTreeMap::new();
+
+    for param in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, raw_value) = match param.find('=') {
+            Some(i) => (&param[..i], &param[i + 1..]),
+            None => return Err(format!("Parameter '{}' has no value", param)),
+        };
+        let key = percent_decode(key)?.to_ascii_lowercase();
+        let param_value = percent_decode(raw_value)?;
+
+        if key == "amount" {
+            if value.is_some() {
+                return Err("Duplicate amount".to_string());
+            }
+            value = Some(parse_erg_amount(&param_value)?.to_string());
+        } else if let Some(token_id) = key.strip_prefix(TOKEN_PARAM_PREFIX) {
+            let amount = check_token(token_id, &param_value, &assets)?;
+            assets.push(AssetValue {
+                token_id: token_id.to_string(),
+                amount: amount.to_string(),
+            });
+        } else if let Some(register) = register_id(&key) {
+            if registers.insert(register.clone(), param_value.to_ascii_lowercase()).is_some() {
+                return Err(format!("Duplicate register: {}", register));
+            }
+        }
+        // other parameters are reserved for future use and ignored
+    }
+    registers_from_hex(&registers)?;
+
+    Ok(TxOutput {
+        value: value.unwrap_or_else(|| "0".to_string()),
+        address: address.to_string(),
+        assets,
+        registers,
+    })
+}
+
+pub(crate) fn output_to_payment_uri(output: &TxOutput, network: Network) -> Result<String, String> {
+    check_address(&output.address, network).map_err(|e| format!("Invalid address: {}", e))?;
+    registers_from_hex(&output.registers)?;
+
+    let mut params: Vec<String> = vec![];
+
+    let value = output
+        .value
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid value: {}", output.value))?;
+    if value > 0 {
+        params.push(format!("amount={}", format_erg_amount(value)));
+    }
+
+    for (i, asset) in output.assets.iter().enumerate() {
+        let token_id = asset.token_id.to_ascii_lowercase();
+        let amount = check_token(&token_id, &asset.amount, &output.assets[..i])?;
+        params.push(format!("{}{}={}", TOKEN_PARAM_PREFIX, token_id, amount));
+    }
+
+    for (id, value) in &output.registers {
+        params.push(format!("{}={}", id.to_ascii_lowercase(), value.to_ascii_lowercase()));
+    }
+
+    let mut uri = format!("{}{}", URI_SCHEME, output.address);
+    if !params.is_empty() {
+        uri.push('?');
+        uri.push_str(&params.join("&"));
+    }
+    Ok(uri)
+}
+
+/// Check token id (lowercase hex) and amount, which must not be zero,
+/// against the tokens already in the request
+fn check_token(token_id: &str, amount: &str, assets: &[AssetValue]) -> Result<u64, String> {
+    if token_id.len() != TOKEN_ID_SIZE * 2 || !token_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid token id: {}", token_id));
+    }
+    if assets.iter().any(|a| a.token_id.eq_ignore_ascii_case(token_id)) {
+        return Err(format!("Duplicate token: {}", token_id));
+    }
+    amount
+        .parse::<u64>()
+        .ok()
+        .filter(|a| *a > 0)
+        .ok_or_else(|| format!("Invalid amount of token {}: {}", token_id, amount))
+}
+
+/// "r4" .. "r9" -> "R4" .. "R9"
+fn register_id(key: &str) -> Option<String> {
+    match key {
+        "r4" | "r5" | "r6" | "r7" | "r8" | "r9" => Some(key.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// Parse decimal ERG amount ("1.5") to nanoERG
+fn parse_erg_amount(amount: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid amount: {}", amount);
+
+    let (int_part, frac_part) = match amount.find('.') {
+        Some(i) => (&amount[..i], &amount[i + 1..]),
+        None => (amount, ""),
+    };
+    if int_part.is_empty()
+        || frac_part.len() > ERG_DECIMALS
+        || !int_part.chars().all(|c| c.is_ascii_digit())
+        || !frac_part.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let frac = format!("{:0<width$}", frac_part, width = ERG_DECIMALS);
+    let nano_ergs = int_part
+        .parse::<u64>()
+        .ok()
+        .and_then(|i| i.checked_mul(NANOERGS_IN_ERG))
+        .and_then(|i| i.checked_add(frac.parse::<u64>().ok()?))
+        .ok_or_else(invalid)?;
+    if nano_ergs == 0 {
+        return Err(invalid());
+    }
+    Ok(nano_ergs)
+}
+
+/// Format nanoERG as decimal ERG amount without trailing zeros
+fn format_erg_amount(nano_ergs: u64) -> String {
+    let int_part = nano_ergs / NANOERGS_IN_ERG;
+    let frac_part = nano_ergs % NANOERGS_IN_ERG;
+    if frac_part == 0 {
+        return int_part.to_string();
+    }
+    let frac = format!("{:0width$}", frac_part, width = ERG_DECIMALS);
+    format!("{}.{}", int_part, frac.trim_end_matches('0'))
+}
+
+fn percent_decode(s: &str) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| format!("Invalid percent encoding: {}", s))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| format!("Invalid percent encoding: {}", s))
+}
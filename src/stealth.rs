@@ -0,0 +1,149 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::collections::BTreeMap;
+
+use generic_array::GenericArray;
+use js_sys::Uint8Array;
+use k256::Scalar;
+use zeroize::Zeroize;
+
+use ergo_lib::chain::ergo_box::{ErgoBox, NonMandatoryRegisterId};
+use ergo_lib::chain::Base16EncodedBytes;
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::{self, EcPoint};
+
+use crate::address::{check_address, Address, Network};
+use crate::message_signing::dlog_secret_from_hex;
+use crate::transaction::TxOutput;
+use crate::utils::{blake2b256, digest32_to_hex};
+
+/// Type code of `GroupElement` constant
+const GROUP_ELEMENT_TYPE: u8 = 0x07;
+const PUBLIC_KEY_SIZE: usize = 33;
+
+/// Stealth meta-address of the secret key (hex encoded) to publish for receiving.
+/// It is the P2PK address of the key, senders never pay to it directly.
+#[wasm_bindgen(js_name = "stealthMetaAddress")]
+pub fn stealth_meta_address(secret: &str) -> Result<String, JsValue> {
+    let pk = dlog_secret_from_hex(secret)?.public_image().h.sigma_serialize_bytes();
+    Ok(Address::from_public_key(&pk)?.get_addr())
+}
+
+/// Output paying `value` (nanoERG) to a fresh one-time address derived from the meta-address.
+///
+/// With ephemeral key `r` and recipient key `P`, the one-time key is `P * g^k`
+/// where `k = blake2b256(P^r)`. `R = g^r` is stored in R4 so that the recipient
+/// can compute the same `k` as `R^x`. The output is the standard P2PK address of the
+/// one-time key: it is recognized as P2PK by wallets and the node, but without the
+/// secret key of the meta-address it can not be linked to `P`.
+#[wasm_bindgen(js_name = "createStealthOutput")]
+pub fn create_stealth_output(meta_address: &str, value: &str, network: Network) -> Result<JsValue, JsValue> {
+    check_address(meta_address, network)?;
+    let pk_bytes = Address::from_base58(meta_address)?
+        .public_key()
+        .ok_or_else(|| JsValue::from_str("Stealth meta-address must be a P2PK address"))?;
+    let pk = EcPoint::sigma_parse_bytes(pk_bytes)
+        .map_err(|e| JsValue::from_str(&format!("Invalid public key: {:?}", e)))?;
+
+    let mut r = DlogProverInput::random().w;
+    let ephemeral_key = dlog_group::exponentiate(&dlog_group::generator(), &r);
+    let k = shared_scalar(&dlog_group::exponentiate(&pk, &r));
+    r.zeroize();
+
+    let one_time_key = pk * &dlog_group::exponentiate(&dlog_group::generator(), &k);
+    let address = Address::from_ergo_tree(&one_time_ergo_tree(&one_time_key), network)?;
+
+    let mut r4 = vec![GROUP_ELEMENT_TYPE];
+    r4.extend_from_slice(&ephemeral_key.sigma_serialize_bytes());
+    let mut registers = BTreeMap::new();
+    registers.insert("R4".to_string(), Base16EncodedBytes::new(r4.as_slice()).into());
+
+    let output = TxOutput {
+        value: value.to_string(),
+        address: address.get_addr(),
+        assets: vec![],
+        registers,
+    };
+    JsValue::from_serde(&output).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
+
+/// Find boxes (JSON) paid to one-time addresses of the secret key (hex encoded).
+/// Returns ids of the matched boxes, `stealthBoxSecret` gives the key to spend each of them.
+#[wasm_bindgen(js_name = "scanStealthBoxes")]
+pub fn scan_stealth_boxes(secret: &str, boxes: Box<[JsValue]>) -> Result<JsValue, JsValue> {
+    let secret = dlog_secret_from_hex(secret)?;
+
+    let mut found: Vec<String> = vec![];
+    for b in boxes.iter() {
+        let ergo_box = parse_box(b)?;
+        if let Some(mut one_time_secret) = one_time_secret(&secret, &ergo_box) {
+            one_time_secret.zeroize();
+            found.push(digest32_to_hex(&ergo_box.box_id().0));
+        }
+    }
+
+    JsValue::from_serde(&found).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
+
+/// One-time secret key spending the stealth box (JSON) of the secret key (hex encoded).
+/// Returns `Uint8Array` the caller can clear (the key is wiped from WASM memory before returning).
+#[wasm_bindgen(js_name = "stealthBoxSecret")]
+pub fn stealth_box_secret(secret: &str, ergo_box: &JsValue) -> Result<Uint8Array, JsValue> {
+    let secret = dlog_secret_from_hex(secret)?;
+    let mut one_time_secret = one_time_secret(&secret, &parse_box(ergo_box)?)
+        .ok_or_else(|| JsValue::from_str("Box is not paid to a one-time address of the secret key"))?;
+
+    let mut bytes = one_time_secret.to_bytes();
+    let result = Uint8Array::from(bytes.as_slice());
+    bytes.as_mut_slice().zeroize();
+    one_time_secret.zeroize();
+    Ok(result)
+}
+
+fn parse_box(ergo_box: &JsValue) -> Result<ErgoBox, JsValue> {
+    ergo_box
+        .into_serde()
+        .map_err(|e| JsValue::from_str(&format!("Invalid box: {}", e)))
+}
+
+/// `x + k` if the box is paid to the one-time key of the secret `x`
+fn one_time_secret(secret: &DlogProverInput, ergo_box: &ErgoBox) -> Option<Scalar> {
+    let ephemeral_key = ephemeral_key(ergo_box)?;
+    let mut k = shared_scalar(&dlog_group::exponentiate(&ephemeral_key, &secret.w));
+    let one_time_key = *secret.public_image().h * &dlog_group::exponentiate(&dlog_group::generator(), &k);
+
+    let result = if ergo_box.ergo_tree.sigma_serialize_bytes() == one_time_ergo_tree(&one_time_key) {
+        Some(secret.w + &k)
+    } else {
+        None
+    };
+    k.zeroize();
+    result
+}
+
+/// `R` from R4, if the box has a group element there
+fn ephemeral_key(ergo_box: &ErgoBox) -> Option<EcPoint> {
+    let bytes = ergo_box
+        .additional_registers
+        .get(NonMandatoryRegisterId::R4)?
+        .sigma_serialize_bytes();
+    if bytes.len() != 1 + PUBLIC_KEY_SIZE || bytes[0] != GROUP_ELEMENT_TYPE {
+        return None;
+    }
+    EcPoint::sigma_parse_bytes(bytes[1..].to_vec()).ok()
+}
+
+/// `k = blake2b256(shared point)` as a scalar
+fn shared_scalar(shared: &EcPoint) -> Scalar {
+    let hash = blake2b256(&shared.sigma_serialize_bytes());
+    Scalar::from_bytes_reduced(GenericArray::from_slice(&hash))
+}
+
+/// ErgoTree of the P2PK address of the key
+fn one_time_ergo_tree(key: &EcPoint) -> Vec<u8> {
+    let mut tree = vec![0x00, 0x08, 0xcd];
+    tree.extend_from_slice(&key.sigma_serialize_bytes());
+    tree
+}
@@ -1,6 +1,8 @@
 use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
 
-use std::convert::{TryFrom, TryInto};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
 
 use ergo_lib::chain::contract::Contract;
 use ergo_lib::chain::ergo_box::BoxId;
@@ -9,15 +11,21 @@ use ergo_lib::chain::ergo_box::NonMandatoryRegisters;
 use ergo_lib::chain::ergo_box::{ErgoBox, ErgoBoxCandidate};
 use ergo_lib::chain::ergo_state_context::ErgoStateContext;
 use ergo_lib::chain::token::{TokenAmount, TokenId, Token};
+use ergo_lib::wallet::box_selector::{BoxSelector, SimpleBoxSelector};
 use ergo_lib::wallet::signing::{sign_transaction, TransactionContext};
 use ergo_lib::chain::Digest32;
 use ergo_lib::chain::Base16DecodedBytes;
-use k256::Scalar;
-use crate::{MINER_ERGO_TREE, MINERS_FEE_MAINNET_ADDRESS};
+use crate::MINERS_FEE_MAINNET_ADDRESS;
+use crate::message_signing::dlog_secret_from_hex;
+use crate::utils::digest32_to_hex;
+use zeroize::Zeroizing;
 use ergo_lib::ergotree_ir::address::{AddressEncoder, NetworkPrefix};
-use ergo_lib::chain::transaction::UnsignedInput;
-use ergo_lib::ergotree_interpreter::sigma_protocol::prover::{ContextExtension, TestProver};
-use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::{PrivateInput, DlogProverInput};
+use ergo_lib::chain::transaction::{Input, TxId, UnsignedInput};
+use ergo_lib::chain::transaction::input::prover_result::ProverResult;
+use ergo_lib::ergotree_interpreter::sigma_protocol::prover::{ContextExtension, ProofBytes, TestProver};
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::ergotree_ir::mir::constant::Constant;
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::PrivateInput;
 use ergo_lib::chain;
 
 
@@ -39,19 +47,78 @@ pub struct TxOutput {
     pub value: String,
     pub address: String,
     pub assets: Vec<AssetValue>,
+    /// Additional registers ("R4" to "R9") as hex encoded constants, densely packed from R4
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub registers: BTreeMap<String, String>,
 }
 
 
 /// Unsigned (inputs without proofs) transaction
 #[wasm_bindgen]
 #[derive(PartialEq, Debug, Clone)]
-pub struct UnsignedTransaction(chain::transaction::unsigned::UnsignedTransaction);
+pub struct UnsignedTransaction(pub(crate) chain::transaction::unsigned::UnsignedTransaction);
 
 #[wasm_bindgen]
 impl UnsignedTransaction {
     pub fn to_json(&self) -> Result<JsValue, JsValue> {
         JsValue::from_serde(&self.0).map_err(|e| JsValue::from_str(&format!("{}", e)))
     }
+
+    /// Parse from JSON representation
+    pub fn from_json(json: &JsValue) -> Result<UnsignedTransaction, JsValue> {
+        json.into_serde::<chain::transaction::unsigned::UnsignedTransaction>()
+            .map(UnsignedTransaction)
+            .map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+
+    /// Transaction id (hex encoded)
+    pub fn tx_id(&self) -> String {
+        tx_id_to_hex(&self.0.id())
+    }
+
+    /// Sigma serialized bytes (same layout as signed transaction with empty proofs)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let inputs = self.0.inputs
+            .iter()
+            .map(|i| Input {
+                box_id: i.box_id.clone(),
+                spending_proof: ProverResult {
+                    proof: ProofBytes::Empty,
+                    extension: i.extension.clone(),
+                },
+            })
+            .collect();
+
+        chain::transaction::Transaction::new(
+            inputs,
+            self.0.data_inputs.clone(),
+            self.0.output_candidates.clone(),
+        ).sigma_serialize_bytes()
+    }
+
+    /// Parse from sigma serialized bytes, inputs must not have proofs
+    pub fn from_bytes(bytes: &[u8]) -> Result<UnsignedTransaction, JsValue> {
+        let tx = chain::transaction::Transaction::sigma_parse_bytes(bytes.to_vec())
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))?;
+
+        let inputs = tx.inputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, i)| match i.spending_proof.proof {
+                ProofBytes::Empty => Ok(UnsignedInput {
+                    box_id: i.box_id,
+                    extension: i.spending_proof.extension,
+                }),
+                _ => Err(JsValue::from_str(&format!("Input {} is signed, expected unsigned transaction", index))),
+            })
+            .collect::<Result<_, JsValue>>()?;
+
+        Ok(UnsignedTransaction(chain::transaction::unsigned::UnsignedTransaction::new(
+            inputs,
+            tx.data_inputs,
+            tx.output_candidates,
+        )))
+    }
 }
 
 impl From<chain::transaction::unsigned::UnsignedTransaction> for UnsignedTransaction {
@@ -67,7 +134,7 @@ impl From<UnsignedTransaction> for chain::transaction::unsigned::UnsignedTransac
 }
 
 #[wasm_bindgen]
-pub struct Transaction(chain::transaction::Transaction);
+pub struct Transaction(pub(crate) chain::transaction::Transaction);
 
 impl From<chain::transaction::Transaction> for Transaction {
     fn from(t: chain::transaction::Transaction) -> Self {
@@ -82,6 +149,53 @@ impl Transaction {
         JsValue::from_serde(&self.0).map_err(|e| JsValue::from_str(&format!("{}", e)))
     }
 
+    /// Parse from JSON representation
+    pub fn from_json(json: &JsValue) -> Result<Transaction, JsValue> {
+        json.into_serde::<chain::transaction::Transaction>()
+            .map(Transaction)
+            .map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
+
+    /// Transaction id (hex encoded)
+    pub fn tx_id(&self) -> String {
+        tx_id_to_hex(&self.0.id())
+    }
+
+    /// Sigma serialized bytes, as expected by the node for raw submission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.sigma_serialize_bytes()
+    }
+
+    /// Parse from sigma serialized bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Transaction, JsValue> {
+        chain::transaction::Transaction::sigma_parse_bytes(bytes.to_vec())
+            .map(Transaction)
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+
+    /// Number of outputs
+    pub fn outputs_len(&self) -> usize {
+        self.0.output_candidates.len()
+    }
+
+    /// Output box at `index` (id is known before the transaction is confirmed)
+    pub fn output(&self, index: usize) -> Result<crate::ErgoBox, JsValue> {
+        self.output_boxes()
+            .into_iter()
+            .nth(index)
+            .map(crate::ErgoBox::from)
+            .ok_or_else(|| JsValue::from_str(&format!("No output with index {}", index)))
+    }
+
+    /// Ids of the boxes created by this transaction (hex encoded)
+    pub fn output_box_ids(&self) -> Box<[JsValue]> {
+        self.output_boxes()
+            .iter()
+            .map(|b| JsValue::from_str(&digest32_to_hex(&b.box_id().0)))
+            .collect::<Vec<JsValue>>()
+            .into_boxed_slice()
+    }
+
     #[wasm_bindgen]
     pub fn create(
         inputs: Box<[JsValue]>,
@@ -89,22 +203,19 @@ impl Transaction {
         fee_amount: u64,
         height: u32,
     ) -> Result<UnsignedTransaction, JsValue> {
-        let fee = match BoxValue::try_from(fee_amount) {
-            Ok(val) => val,
-            Err(err) => return Err(JsValue::from_str(&format!("Wrong fee amount: {:?}", err)))
-        };
+        if let Err(err) = BoxValue::try_from(fee_amount) {
+            return Err(JsValue::from_str(&format!("Wrong fee amount: {:?}", err)));
+        }
 
         let inputs_from_js: Vec<TxInput> = inputs
-            .into_iter()
+            .iter()
             .map(|x| x.into_serde().unwrap())
             .collect();
 
         let outputs_from_js: Vec<TxOutput> = outputs
-            .into_iter()
-            .map(|x| x.into_serde().unwrap())
-            .collect();
-
-        let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
+            .iter()
+            .map(|x| x.into_serde().map_err(|e| JsValue::from_str(&format!("Invalid output: {}", e))))
+            .collect::<Result<_, _>>()?;
 
         // construct inputs without proofs
         let _inputs: Vec<UnsignedInput> = inputs_from_js
@@ -122,37 +233,7 @@ impl Transaction {
             .collect();
 
         // construct outputs
-        let mut _outputs: Vec<ErgoBoxCandidate> = outputs_from_js
-            .iter()
-            .map(|x| {
-                let addr = encoder.parse_address_from_str(x.address.as_str()).unwrap();
-                let contract = Contract::pay_to_address(&addr).unwrap();
-
-                let val = x.value.parse::<u64>().unwrap();
-
-                // tokens
-                let tokens = x
-                    .assets
-                    .iter()
-                    .map(|t| {
-                        let id_bytes = Base16DecodedBytes::try_from(t.token_id.clone()).unwrap();
-                        let digest = Digest32::try_from(id_bytes).unwrap();
-                        Token {
-                            token_id: TokenId(digest),
-                            amount: TokenAmount::try_from(t.amount.parse::<u64>().unwrap()).unwrap(),
-                        }
-                    })
-                    .collect();
-
-                ErgoBoxCandidate {
-                    value: BoxValue::new(val).unwrap(),
-                    ergo_tree: contract.ergo_tree(),
-                    tokens,
-                    additional_registers: NonMandatoryRegisters::empty(),
-                    creation_height: height,
-                }
-            })
-            .collect();
+        let mut _outputs = Self::output_candidates(&outputs_from_js, height)?;
 
         // add one output for miner fee
         _outputs.push(Self::fee_box_candidate(fee_amount, height));
@@ -169,31 +250,166 @@ impl Transaction {
         boxes_to_spend: Box<[JsValue]>,
         tx: &JsValue,
     ) -> Result<Transaction, JsValue> {
-        let secrets: Vec<String> = secret_keys
-            .into_iter()
+        let secrets: Vec<Zeroizing<String>> = secret_keys
+            .iter()
+            .map(|x| x.as_string().map(Zeroizing::new).ok_or_else(|| JsValue::from_str("Secret key must be a string")))
+            .collect::<Result<_, _>>()?;
+
+        let boxes_to_spend: Vec<ErgoBox> = boxes_to_spend
+            .iter()
             .map(|x| x.into_serde().unwrap())
             .collect();
 
-        let boxes_to_spend: Vec<ErgoBox> = boxes_to_spend
+        let unsigned: chain::transaction::unsigned::UnsignedTransaction = tx.into_serde().unwrap();
+
+        Self::sign_with_boxes(secrets, boxes_to_spend, unsigned)
+    }
+
+    /// Build transaction which may spend outputs of the `parent` transaction
+    /// that is signed but not confirmed yet.
+    ///
+    /// Inputs are selected from `unspent_boxes` (JSON) and the parent outputs
+    /// protected by the change address. Change goes to `change_address`.
+    #[wasm_bindgen]
+    pub fn create_chained(
+        parent: &Transaction,
+        unspent_boxes: Box<[JsValue]>,
+        outputs: Box<[JsValue]>,
+        fee_amount: u64,
+        change_address: &str,
+        height: u32,
+    ) -> Result<UnsignedTransaction, JsValue> {
+        let fee = BoxValue::try_from(fee_amount)
+            .map_err(|err| JsValue::from_str(&format!("Wrong fee amount: {:?}", err)))?;
+
+        let unspent: Vec<ErgoBox> = unspent_boxes
+            .iter()
+            .map(|x| x.into_serde().map_err(|e| JsValue::from_str(&format!("Invalid box: {}", e))))
+            .collect::<Result<_, _>>()?;
+
+        let outputs_from_js: Vec<TxOutput> = outputs
+            .iter()
+            .map(|x| x.into_serde().map_err(|e| JsValue::from_str(&format!("Invalid output: {}", e))))
+            .collect::<Result<_, _>>()?;
+
+        let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
+        let change_addr = encoder
+            .parse_address_from_str(change_address)
+            .map_err(|e| JsValue::from_str(&format!("Invalid change address: {:?}", e)))?;
+        let change_tree = Contract::pay_to_address(&change_addr)
+            .map_err(|e| JsValue::from_str(&format!("Invalid change address: {:?}", e)))?
+            .ergo_tree();
+
+        // boxes spent by the parent are not available anymore, its outputs are
+        let available: Vec<ErgoBox> = unspent
             .into_iter()
-            .map(|x| x.into_serde().unwrap())
+            .filter(|b| !parent.0.inputs.iter().any(|i| i.box_id == b.box_id()))
+            .chain(parent.output_boxes().into_iter().filter(|b| b.ergo_tree == change_tree))
+            .collect();
+
+        let mut _outputs = Self::output_candidates(&outputs_from_js, height)?;
+
+        // target balance and tokens to cover
+        let mut target: u64 = u64::from(fee);
+        let mut target_tokens: Vec<Token> = vec![];
+        for output in _outputs.iter() {
+            target = target
+                .checked_add(u64::from(output.value))
+                .ok_or_else(|| JsValue::from_str("Outputs value overflow"))?;
+            for token in output.tokens.iter() {
+                match target_tokens.iter_mut().find(|t| t.token_id == token.token_id) {
+                    Some(t) => {
+                        let amount = u64::from(t.amount)
+                            .checked_add(u64::from(token.amount))
+                            .ok_or_else(|| JsValue::from_str("Token amount overflow"))?;
+                        t.amount = TokenAmount::try_from(amount)
+                            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))?;
+                    }
+                    None => target_tokens.push(token.clone()),
+                }
+            }
+        }
+        let target = BoxValue::try_from(target)
+            .map_err(|e| JsValue::from_str(&format!("Wrong outputs value: {:?}", e)))?;
+
+        let selection = SimpleBoxSelector::new()
+            .select(available, target, &target_tokens)
+            .map_err(|e| JsValue::from_str(&format!("Cannot select boxes: {}", e)))?;
+
+        for change in selection.change_boxes.iter() {
+            _outputs.push(ErgoBoxCandidate {
+                value: change.value,
+                ergo_tree: change_tree.clone(),
+                tokens: change.tokens.clone(),
+                additional_registers: NonMandatoryRegisters::empty(),
+                creation_height: height,
+            });
+        }
+
+        // add one output for miner fee
+        _outputs.push(Self::fee_box_candidate(fee_amount, height));
+
+        let _inputs: Vec<UnsignedInput> = selection.boxes
+            .iter()
+            .map(|b| UnsignedInput {
+                box_id: b.box_id(),
+                extension: ContextExtension::empty(),
+            })
             .collect();
 
+        let tx = chain::transaction::unsigned::UnsignedTransaction::new(_inputs, vec![], _outputs);
+
+        Ok(UnsignedTransaction(tx))
+    }
+
+    /// Sign transaction which may spend outputs of the unconfirmed `parent` transaction
+    #[wasm_bindgen]
+    pub fn sign_chained(
+        secret_keys: Box<[JsValue]>,
+        parent: &Transaction,
+        boxes_to_spend: Box<[JsValue]>,
+        tx: &UnsignedTransaction,
+    ) -> Result<Transaction, JsValue> {
+        let secrets: Vec<Zeroizing<String>> = secret_keys
+            .iter()
+            .map(|x| x.as_string().map(Zeroizing::new).ok_or_else(|| JsValue::from_str("Secret key must be a string")))
+            .collect::<Result<_, _>>()?;
+
+        let mut boxes: Vec<ErgoBox> = boxes_to_spend
+            .iter()
+            .map(|x| x.into_serde().map_err(|e| JsValue::from_str(&format!("Invalid box: {}", e))))
+            .collect::<Result<_, _>>()?;
+        boxes.extend(parent.output_boxes());
+
+        // keep only boxes spent by the transaction, in the order of its inputs
+        let boxes_to_spend = tx.0.inputs
+            .iter()
+            .map(|i| {
+                boxes
+                    .iter()
+                    .find(|b| b.box_id() == i.box_id)
+                    .cloned()
+                    .ok_or_else(|| JsValue::from_str("Input box is missing"))
+            })
+            .collect::<Result<Vec<ErgoBox>, JsValue>>()?;
+
+        Self::sign_with_boxes(secrets, boxes_to_spend, tx.0.clone())
+    }
+
+    fn sign_with_boxes(
+        secrets: Vec<Zeroizing<String>>,
+        boxes_to_spend: Vec<ErgoBox>,
+        unsigned: chain::transaction::unsigned::UnsignedTransaction,
+    ) -> Result<Transaction, JsValue> {
         // 1. Construct prover from secret keys
         let prover = TestProver {
             secrets: secrets
-                .into_iter()
-                .map(|s| {
-                    let scalar_bytes = Base16DecodedBytes::try_from(s).unwrap();
-                    let bytes: &[u8; 32] = scalar_bytes.0.as_slice().try_into().unwrap();
-
-                    PrivateInput::DlogProverInput(DlogProverInput::from_bytes(bytes).unwrap())
-                })
-                .collect(),
+                .iter()
+                .map(|s| dlog_secret_from_hex(s).map(PrivateInput::DlogProverInput))
+                .collect::<Result<_, _>>()?,
         };
 
         // 2. Construct unsigned transaction
-        let unsigned: chain::transaction::unsigned::UnsignedTransaction = tx.into_serde().unwrap();
         let tx_context = TransactionContext {
             spending_tx: unsigned,
             boxes_to_spend,
@@ -211,6 +427,65 @@ impl Transaction {
         res
     }
 
+    pub(crate) fn output_boxes(&self) -> Vec<ErgoBox> {
+        let tx_id = self.0.id();
+        self.0.output_candidates
+            .iter()
+            .enumerate()
+            .map(|(index, candidate)| ErgoBox::from_box_candidate(candidate, tx_id.clone(), index as u16))
+            .collect()
+    }
+
+    fn output_candidates(outputs_from_js: &[TxOutput], height: u32) -> Result<Vec<ErgoBoxCandidate>, JsValue> {
+        let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
+
+        outputs_from_js
+            .iter()
+            .map(|x| {
+                let addr = encoder
+                    .parse_address_from_str(x.address.as_str())
+                    .map_err(|e| JsValue::from_str(&format!("Invalid output address {}: {:?}", x.address, e)))?;
+                let contract = Contract::pay_to_address(&addr)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid output address {}: {:?}", x.address, e)))?;
+
+                let value = x.value
+                    .parse::<u64>()
+                    .ok()
+                    .and_then(|v| BoxValue::new(v).ok())
+                    .ok_or_else(|| JsValue::from_str(&format!("Invalid output value: {}", x.value)))?;
+
+                // tokens
+                let tokens = x
+                    .assets
+                    .iter()
+                    .map(|t| {
+                        let id_bytes = Base16DecodedBytes::try_from(t.token_id.clone())
+                            .map_err(|_| JsValue::from_str(&format!("Invalid token id: {}", t.token_id)))?;
+                        let digest = Digest32::try_from(id_bytes)
+                            .map_err(|_| JsValue::from_str(&format!("Invalid token id: {}", t.token_id)))?;
+                        let amount = t.amount
+                            .parse::<u64>()
+                            .ok()
+                            .and_then(|a| TokenAmount::try_from(a).ok())
+                            .ok_or_else(|| JsValue::from_str(&format!("Invalid amount of token {}: {}", t.token_id, t.amount)))?;
+                        Ok(Token {
+                            token_id: TokenId(digest),
+                            amount,
+                        })
+                    })
+                    .collect::<Result<Vec<Token>, JsValue>>()?;
+
+                Ok(ErgoBoxCandidate {
+                    value,
+                    ergo_tree: contract.ergo_tree(),
+                    tokens,
+                    additional_registers: registers_from_hex(&x.registers).map_err(|e| JsValue::from_str(&e))?,
+                    creation_height: height,
+                })
+            })
+            .collect()
+    }
+
     fn fee_box_candidate(fee_amount: u64, creation_height: u32) -> ErgoBoxCandidate {
         let address_encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
         let miner_fee_address = address_encoder
@@ -218,8 +493,6 @@ impl Transaction {
             .unwrap();
         let fee_ergo_tree = miner_fee_address.script().unwrap();
 
-        //let ergo_tree_bytes = Base16DecodedBytes::try_from(MINER_ERGO_TREE.to_string()).unwrap();
-        //let fee_ergo_tree = ErgoTree::sigma_parse_bytes(ergo_tree_bytes.0).unwrap();
         ErgoBoxCandidate {
             value: BoxValue::new(fee_amount).unwrap(),
             ergo_tree: fee_ergo_tree,
@@ -230,4 +503,24 @@ impl Transaction {
     }
 }
 
+/// Build registers from `TxOutput::registers`, which must start at R4 without gaps
+pub(crate) fn registers_from_hex(registers: &BTreeMap<String, String>) -> Result<NonMandatoryRegisters, String> {
+    let mut values = vec![];
+    for (i, (id, value)) in registers.iter().enumerate() {
+        let expected = format!("R{}", i + 4);
+        if *id != expected {
+            return Err(format!("Register {} is out of order, expected {}", id, expected));
+        }
+        let bytes = Base16DecodedBytes::try_from(value.clone())
+            .map_err(|_| format!("Register {} is not a valid hex string", id))?;
+        let constant = Constant::sigma_parse_bytes(bytes.0)
+            .map_err(|e| format!("Register {} is not a valid constant: {:?}", id, e))?;
+        values.push(constant);
+    }
+
+    NonMandatoryRegisters::from_ordered_values(values).map_err(|e| format!("Invalid registers: {:?}", e))
+}
 
+fn tx_id_to_hex(tx_id: &TxId) -> String {
+    digest32_to_hex(&tx_id.0)
+}
@@ -0,0 +1,264 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+use ergo_lib::chain::ergo_box::{ErgoBox, ErgoBoxCandidate};
+use ergo_lib::chain::token::Token;
+use ergo_lib::ergotree_ir::address::{Address, AddressEncoder, NetworkPrefix};
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+use crate::transaction::{AssetValue, UnsignedTransaction};
+use crate::utils::digest32_to_hex;
+use crate::MINERS_FEE_MAINNET_ADDRESS;
+
+/// Output going outside of the wallet
+#[derive(Serialize, Deserialize)]
+pub struct TxRecipient {
+    /// Output index in the transaction
+    pub index: u32,
+    pub address: String,
+    pub value: String,
+    pub assets: Vec<AssetValue>,
+}
+
+/// Change of the balance of one wallet address
+#[derive(Serialize, Deserialize)]
+pub struct WalletChange {
+    pub address: String,
+    #[serde(rename = "ergChange")]
+    pub erg_change: String,
+    #[serde(rename = "tokenChanges")]
+    pub token_changes: Vec<AssetValue>,
+}
+
+/// What the transaction does from the wallet point of view.
+/// Amounts of changes are signed: negative means the wallet is spending.
+#[derive(Serialize, Deserialize)]
+pub struct TxSummary {
+    #[serde(rename = "ergChange")]
+    pub erg_change: String,
+    #[serde(rename = "tokenChanges")]
+    pub token_changes: Vec<AssetValue>,
+    /// Changes per wallet address, in the order the addresses were given
+    #[serde(rename = "walletChanges")]
+    pub wallet_changes: Vec<WalletChange>,
+    pub recipients: Vec<TxRecipient>,
+    pub fee: String,
+    /// Tokens sent to the miner together with the fee
+    #[serde(rename = "feeAssets")]
+    pub fee_assets: Vec<AssetValue>,
+    pub burned: Vec<AssetValue>,
+    pub minted: Vec<AssetValue>,
+    /// Indexes of outputs protected by scripts other than P2PK
+    #[serde(rename = "unknownScriptOutputs")]
+    pub unknown_script_outputs: Vec<u32>,
+}
+
+/// Summarize unsigned transaction for the confirmation screen.
+///
+/// `input_boxes` are the boxes spent by the transaction (JSON),
+/// `wallet_addresses` are the addresses belonging to the wallet.
+#[wasm_bindgen(js_name = "summarizeTransaction")]
+pub fn summarize_transaction(
+    tx: &UnsignedTransaction,
+    input_boxes: Box<[JsValue]>,
+    wallet_addresses: Box<[JsValue]>,
+) -> Result<JsValue, JsValue> {
+    let boxes: Vec<ErgoBox> = input_boxes
+        .iter()
+        .map(|x| x.into_serde().map_err(|e| JsValue::from_str(&format!("Invalid input box: {}", e))))
+        .collect::<Result<_, _>>()?;
+
+    let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
+
+    // ErgoTree -> index in `wallet`
+    let mut wallet_trees: HashMap<Vec<u8>, usize> = HashMap::new();
+    let mut wallet: Vec<(String, Balance)> = vec![];
+    for js_addr in wallet_addresses.iter() {
+        let addr: String = js_addr
+            .into_serde()
+            .map_err(|e| JsValue::from_str(&format!("Invalid wallet address: {}", e)))?;
+        let tree = address_tree_bytes(&encoder, &addr)?;
+        if let Entry::Vacant(e) = wallet_trees.entry(tree) {
+            e.insert(wallet.len());
+            wallet.push((addr, Balance::default()));
+        }
+    }
+    let fee_tree = address_tree_bytes(&encoder, MINERS_FEE_MAINNET_ADDRESS)?;
+
+    let mut inputs: Vec<&ErgoBox> = vec![];
+    for input in tx.0.inputs.iter() {
+        let b = boxes
+            .iter()
+            .find(|b| b.box_id() == input.box_id)
+            .ok_or_else(|| JsValue::from_str("Input box is missing"))?;
+        inputs.push(b);
+    }
+
+    let mut token_balance: BTreeMap<String, i128> = BTreeMap::new();
+
+    for b in inputs.iter() {
+        if let Some(i) = wallet_trees.get(&b.ergo_tree.sigma_serialize_bytes()) {
+            wallet[*i].1.spend(u64::from(b.value), &b.tokens);
+        }
+        for t in b.tokens.iter() {
+            let (id, amount) = token_entry(t);
+            *token_balance.entry(id).or_insert(0) += amount;
+        }
+    }
+
+    // new token may only have id of the first input
+    let minting_id = inputs.first().map(|b| digest32_to_hex(&b.box_id().0));
+
+    let mut recipients = vec![];
+    let mut unknown_script_outputs = vec![];
+    let mut fee: u64 = 0;
+    let mut fee_assets: BTreeMap<String, i128> = BTreeMap::new();
+
+    for (index, output) in tx.0.output_candidates.iter().enumerate() {
+        let index = index as u32;
+        let tree_bytes = output.ergo_tree.sigma_serialize_bytes();
+        let value = u64::from(output.value);
+
+        for t in output.tokens.iter() {
+            let (id, amount) = token_entry(t);
+            *token_balance.entry(id).or_insert(0) -= amount;
+        }
+
+        if tree_bytes == fee_tree {
+            fee += value;
+            for t in output.tokens.iter() {
+                let (id, amount) = token_entry(t);
+                *fee_assets.entry(id).or_insert(0) += amount;
+            }
+            continue;
+        }
+
+        if let Some(i) = wallet_trees.get(&tree_bytes) {
+            wallet[*i].1.receive(value, &output.tokens);
+            continue;
+        }
+
+        let address = Address::recreate_from_ergo_tree(&output.ergo_tree).ok();
+        match address {
+            Some(Address::P2PK(_)) => {}
+            _ => unknown_script_outputs.push(index),
+        }
+
+        recipients.push(TxRecipient {
+            index,
+            address: address.map(|a| encoder.address_to_str(&a)).unwrap_or_default(),
+            value: value.to_string(),
+            assets: output_assets(output),
+        });
+    }
+
+    let mut burned = vec![];
+    let mut minted = vec![];
+    for (id, balance) in token_balance {
+        if balance > 0 {
+            burned.push(asset_value(id, balance));
+        } else if balance < 0 && Some(&id) == minting_id.as_ref() {
+            minted.push(asset_value(id, -balance));
+        }
+    }
+
+    let mut total = Balance::default();
+    for (_, balance) in wallet.iter() {
+        total.add(balance);
+    }
+
+    let summary = TxSummary {
+        erg_change: total.erg.to_string(),
+        token_changes: total.token_changes(),
+        wallet_changes: wallet
+            .into_iter()
+            .map(|(address, balance)| WalletChange {
+                address,
+                erg_change: balance.erg.to_string(),
+                token_changes: balance.token_changes(),
+            })
+            .collect(),
+        recipients,
+        fee: fee.to_string(),
+        fee_assets: fee_assets.into_iter().map(|(id, amount)| asset_value(id, amount)).collect(),
+        burned,
+        minted,
+        unknown_script_outputs,
+    };
+
+    JsValue::from_serde(&summary).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
+
+/// Signed change of ERG and token amounts
+#[derive(Default)]
+struct Balance {
+    erg: i128,
+    tokens: BTreeMap<String, i128>,
+}
+
+impl Balance {
+    fn spend(&mut self, value: u64, tokens: &[Token]) {
+        self.erg -= value as i128;
+        for t in tokens {
+            let (id, amount) = token_entry(t);
+            *self.tokens.entry(id).or_insert(0) -= amount;
+        }
+    }
+
+    fn receive(&mut self, value: u64, tokens: &[Token]) {
+        self.erg += value as i128;
+        for t in tokens {
+            let (id, amount) = token_entry(t);
+            *self.tokens.entry(id).or_insert(0) += amount;
+        }
+    }
+
+    fn add(&mut self, other: &Balance) {
+        self.erg += other.erg;
+        for (id, amount) in other.tokens.iter() {
+            *self.tokens.entry(id.clone()).or_insert(0) += amount;
+        }
+    }
+
+    fn token_changes(&self) -> Vec<AssetValue> {
+        self.tokens
+            .iter()
+            .filter(|(_, amount)| **amount != 0)
+            .map(|(id, amount)| asset_value(id.clone(), *amount))
+            .collect()
+    }
+}
+
+fn address_tree_bytes(encoder: &AddressEncoder, address: &str) -> Result<Vec<u8>, JsValue> {
+    let tree: ErgoTree = encoder
+        .parse_address_from_str(address)
+        .map_err(|e| JsValue::from_str(&format!("Invalid address {}: {:?}", address, e)))?
+        .script()
+        .map_err(|e| JsValue::from_str(&format!("{:?}", e)))?;
+    Ok(tree.sigma_serialize_bytes())
+}
+
+fn token_entry(token: &Token) -> (String, i128) {
+    (digest32_to_hex(&token.token_id.0), u64::from(token.amount) as i128)
+}
+
+fn output_assets(output: &ErgoBoxCandidate) -> Vec<AssetValue> {
+    output.tokens
+        .iter()
+        .map(|t| {
+            let (id, amount) = token_entry(t);
+            asset_value(id, amount)
+        })
+        .collect()
+}
+
+fn asset_value(token_id: String, amount: i128) -> AssetValue {
+    AssetValue {
+        token_id,
+        amount: amount.to_string(),
+    }
+}
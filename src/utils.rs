@@ -1,3 +1,9 @@
+use blake2::VarBlake2b;
+use blake2::digest::{Update, VariableOutput};
+use ergo_lib::chain::{Base16EncodedBytes, Digest32};
+use zeroize::Zeroizing;
+
+#[allow(dead_code)]
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -8,3 +14,40 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+/// Blake2b hash with 256-bit output, the hash function used throughout Ergo
+pub fn blake2b256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = VarBlake2b::new(32).unwrap();
+    hasher.update(data);
+
+    let mut hash = [0u8; 32];
+    hasher.finalize_variable(|res| hash.copy_from_slice(res));
+    hash
+}
+
+/// Hex representation of a digest (box id, token id, transaction id)
+pub fn digest32_to_hex(digest: &Digest32) -> String {
+    Base16EncodedBytes::new(&digest.0[..]).into()
+}
+
+/// Bytes from the system CSPRNG (`crypto.getRandomValues` in browser, `crypto` module in Node.js)
+pub fn random_bytes(len: usize) -> Result<Vec<u8>, getrandom::Error> {
+    let mut bytes = vec![0u8; len];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decode hex encoded secret into a buffer which is wiped on drop
+pub fn secret_from_hex(hex: &str) -> Option<Zeroizing<Vec<u8>>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let mut bytes = Zeroizing::new(Vec::with_capacity(hex.len() / 2));
+    for pair in hex.as_bytes().chunks(2) {
+        let high = (pair[0] as char).to_digit(16)?;
+        let low = (pair[1] as char).to_digit(16)?;
+        bytes.push((high * 16 + low) as u8);
+    }
+    Some(bytes)
+}
@@ -0,0 +1,112 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use ergo_lib::chain::{Base16DecodedBytes, Base16EncodedBytes};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::password_crypto::{decrypt_envelope, encrypt_envelope_random, parse_optional_kdf, Kdf, ROUNDS};
+
+pub const WALLET_FILE_VERSION: u32 = 1;
+
+/// Content of the wallet which is kept encrypted
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct WalletSecret {
+    /// Mnemonic phrase, if the wallet was created or restored from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    /// Seed (hex encoded), if the wallet was imported without mnemonic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<String>,
+    /// Indexes of the derived accounts
+    pub accounts: Vec<u32>,
+    /// Address labels (address -> label)
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// "mainnet" or "testnet"
+    pub network: String,
+}
+
+impl Drop for WalletSecret {
+    fn drop(&mut self) {
+        self.mnemonic.zeroize();
+        self.seed.zeroize();
+    }
+}
+
+/// Wallet keystore: plaintext header and the encrypted `WalletSecret`
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct WalletFile {
+    pub version: u32,
+    pub name: String,
+    /// Encrypted envelope (see `passwordEncrypt`), hex encoded
+    pub secret: String,
+}
+
+impl WalletFile {
+    pub(crate) fn seal(
+        name: &str,
+        password: &str,
+        secret: &WalletSecret,
+        kdf: Kdf,
+    ) -> Result<WalletFile, JsValue> {
+        if secret.mnemonic.is_none() && secret.seed.is_none() {
+            return Err(JsValue::from_str("Wallet must contain mnemonic or seed"));
+        }
+
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(secret).map_err(|e| JsValue::from_str(&format!("{}", e)))?
+        );
+        let mut file = WalletFile {
+            version: WALLET_FILE_VERSION,
+            name: name.to_string(),
+            secret: String::new(),
+        };
+        let envelope = encrypt_envelope_random(password, kdf, &plaintext, &file.header_aad())?;
+        file.secret = Base16EncodedBytes::new(envelope.to_bytes().as_slice()).into();
+
+        Ok(file)
+    }
+
+    pub(crate) fn open(&self, password: &str) -> Result<WalletSecret, JsValue> {
+        if self.version != WALLET_FILE_VERSION {
+            return Err(JsValue::from_str(&format!("Unsupported wallet file version: {}", self.version)));
+        }
+
+        let encrypted = Base16DecodedBytes::try_from(self.secret.clone())
+            .map_err(|_| JsValue::from_str("Wallet secret is not a valid hex string"))?;
+        let (_, plaintext) = decrypt_envelope(password, &encrypted.0, &self.header_aad())?;
+
+        serde_json::from_slice(&plaintext).map_err(|e| JsValue::from_str(&format!("Invalid wallet content: {}", e)))
+    }
+
+    /// Plaintext header authenticated together with the secret,
+    /// so that tampering with the name or version is detected
+    fn header_aad(&self) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "version": self.version,
+            "name": self.name,
+        })).unwrap()
+    }
+}
+
+/// Create encrypted wallet file from `WalletSecret` JSON.
+/// `kdf` is optional (see `passwordEncrypt`).
+#[wasm_bindgen(js_name = "createWalletFile")]
+pub fn create_wallet_file(
+    name: &str,
+    password: &str,
+    secret: &JsValue,
+    kdf: &JsValue,
+) -> Result<JsValue, JsValue> {
+    let secret: WalletSecret = secret
+        .into_serde()
+        .map_err(|e| JsValue::from_str(&format!("Invalid wallet content: {}", e)))?;
+    let kdf = parse_optional_kdf(kdf)?.unwrap_or(Kdf::Pbkdf2HmacSha512 { rounds: ROUNDS });
+
+    let file = WalletFile::seal(name, password, &secret, kdf)?;
+
+    JsValue::from_serde(&file).map_err(|e| JsValue::from_str(&format!("{}", e)))
+}
@@ -0,0 +1,171 @@
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+extern crate wasm_bindgen;
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use ergowallet_wasm::*;
+use ergo_lib::chain::{Base16DecodedBytes, Base16EncodedBytes};
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+use std::convert::TryFrom;
+
+const MINERS_FEE_MAINNET_ADDRESS: &str =
+    "2iHkR7CWvD1R4j1yZg5bkeDRQavjAaVPeTDFGGLZduHyfWMuYpmhHocX8GJoaieTx78FntzJbCBVL6rf96ocJoZdmWBL2fci7NqWgAirppPQmZ7fN9V6z13Ay6brPriBKYqLp1bT2Fk4FkFLCfdPpe";
+const MINER_ERGO_TREE: &str = "1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304";
+
+fn random_public_key() -> Vec<u8> {
+    let dpi = DlogProverInput::random();
+    let secret: String = Base16EncodedBytes::new(dpi.w.to_bytes().as_slice()).into();
+    public_from_secret(&secret)
+}
+
+#[wasm_bindgen_test]
+fn p2pk_address_introspection() {
+    let pk = random_public_key();
+    let address = Address::from_base58(&Address::from_public_key(&pk).unwrap().get_addr()).unwrap();
+
+    assert_eq!(address.address_type(), AddressType::P2PK);
+    assert_eq!(address.network(), Network::Mainnet);
+    assert_eq!(address.public_key(), Some(pk.clone()));
+    assert_eq!(address.script_hash(), None);
+
+    let tree = address.ergo_tree();
+    assert_eq!(&tree[..3], &[0x00, 0x08, 0xcd]);
+    assert_eq!(&tree[3..], &pk[..]);
+
+    let testnet = Address::from_ergo_tree(&tree, Network::Testnet).unwrap();
+    assert_eq!(testnet.network(), Network::Testnet);
+    assert_eq!(testnet.public_key(), Some(pk));
+    assert!(!Address::validate(&testnet.get_addr()));
+}
+
+#[wasm_bindgen_test]
+fn p2s_address_introspection() {
+    let tree = Base16DecodedBytes::try_from(MINER_ERGO_TREE.to_string()).unwrap().0;
+    let address = Address::from_ergo_tree(&tree, Network::Mainnet).unwrap();
+
+    assert_eq!(address.get_addr(), MINERS_FEE_MAINNET_ADDRESS);
+    assert_eq!(address.address_type(), AddressType::P2S);
+    assert_eq!(address.public_key(), None);
+    assert_eq!(address.ergo_tree(), tree);
+}
+
+#[wasm_bindgen_test]
+fn address_from_invalid_input() {
+    assert!(Address::from_ergo_tree(&[0xff, 0x01], Network::Mainnet).is_err());
+
+    // P2PK template with a point which is not on the curve
+    let mut p2pk_tree = vec![0x00, 0x08, 0xcd, 0x02];
+    p2pk_tree.extend_from_slice(&[0xff; 32]);
+    assert!(Address::from_ergo_tree(&p2pk_tree, Network::Mainnet).is_err());
+
+    let mut address = MINERS_FEE_MAINNET_ADDRESS.to_string();
+    address.pop();
+    assert!(Address::from_base58(&address).is_err());
+}
+
+fn validation(address: &str, network: Network) -> AddressValidation {
+    validate_address(address, network).unwrap().into_serde().unwrap()
+}
+
+#[wasm_bindgen_test]
+fn address_validation_diagnostics() {
+    assert!(validation(MINERS_FEE_MAINNET_ADDRESS, Network::Mainnet).valid);
+
+    let result = validation(MINERS_FEE_MAINNET_ADDRESS, Network::Testnet);
+    assert!(!result.valid);
+    assert_eq!(result.reason, Some("WrongNetwork".to_string()));
+
+    let mut invalid_char = MINERS_FEE_MAINNET_ADDRESS.to_string();
+    invalid_char.replace_range(5..6, "0");
+    let result = validation(&invalid_char, Network::Mainnet);
+    assert_eq!(result.reason, Some("InvalidCharacter".to_string()));
+    assert_eq!(result.position, Some(5));
+
+    let mistyped = MINERS_FEE_MAINNET_ADDRESS.replacen("2iHk", "2iHm", 1);
+    let result = validation(&mistyped, Network::Mainnet);
+    assert_eq!(result.reason, Some("ChecksumMismatch".to_string()));
+}
+
+#[wasm_bindgen_test]
+fn address_from_uncompressed_public_key() {
+    let compressed = Base16DecodedBytes::try_from(
+        "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".to_string(),
+    ).unwrap().0;
+    let uncompressed = Base16DecodedBytes::try_from(
+        "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+         483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8".to_string(),
+    ).unwrap().0;
+
+    let address = Address::from_public_key(&uncompressed).unwrap();
+    assert_eq!(address.get_addr(), Address::from_public_key(&compressed).unwrap().get_addr());
+    assert_eq!(address.public_key(), Some(compressed.clone()));
+
+    assert!(Address::from_public_key(&compressed[..32]).is_err());
+    let mut off_curve = uncompressed.clone();
+    off_curve[64] ^= 1;
+    assert!(Address::from_public_key(&off_curve).is_err());
+}
+
+#[wasm_bindgen_test]
+fn p2s_p2sh_address_from_ergo_tree() {
+    let p2s = Address::p2s_from_ergo_tree_hex(MINER_ERGO_TREE, Network::Mainnet).unwrap();
+    assert_eq!(p2s.get_addr(), MINERS_FEE_MAINNET_ADDRESS);
+
+    // the P2SH hash needs the parsed proposition, which ergo-lib 0.8 can't produce for the miner fee script
+    let script = "100108cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f817987300";
+    let p2sh = Address::p2sh_from_ergo_tree_hex(script, Network::Testnet).unwrap();
+    assert_eq!(p2sh.address_type(), AddressType::P2SH);
+    assert_eq!(p2sh.network(), Network::Testnet);
+    assert_eq!(p2sh.script_hash().unwrap().len(), 24);
+
+    // ErgoTree of P2SH address resolves back to the same address
+    let p2sh_tree = Address::from_ergo_tree(&p2sh.ergo_tree(), Network::Testnet).unwrap();
+    assert_eq!(p2sh_tree.get_addr(), p2sh.get_addr());
+
+    assert!(Address::p2s_from_ergo_tree_hex("not hex", Network::Mainnet).is_err());
+}
+
+#[wasm_bindgen_test]
+fn p2sh_address_hashes_proposition() {
+    // proveDlog(G): the script hash is blake2b256(08cd || G)[..24], the serialized
+    // proposition as hashed by the node's Pay2SHAddress, not the ErgoTree bytes
+    let p2pk_tree = "0008cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    let segregated_tree = "100108cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f817987300";
+
+    let p2sh = Address::p2sh_from_ergo_tree_hex(p2pk_tree, Network::Mainnet).unwrap();
+    assert_eq!(p2sh.get_addr(), "771ToRd4uvYu9qGsKm7ibFM5Sc46QgSegD1aB4Q");
+    assert_eq!(
+        p2sh.script_hash().unwrap(),
+        Base16DecodedBytes::try_from("516e29a942fd05ed9bb83e739dad977cc44f9f5a59f25f25".to_string()).unwrap().0
+    );
+
+    let from_segregated = Address::p2sh_from_ergo_tree_hex(segregated_tree, Network::Mainnet).unwrap();
+    assert_eq!(from_segregated.get_addr(), p2sh.get_addr());
+}
+
+#[wasm_bindgen_test]
+fn batch_validation_and_derivation() {
+    let addresses = vec![MINERS_FEE_MAINNET_ADDRESS.to_string(), "not an address".to_string()];
+    let results: Vec<AddressValidation> =
+        validate_addresses(&JsValue::from_serde(&addresses).unwrap(), Network::Mainnet)
+            .unwrap()
+            .into_serde()
+            .unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].valid);
+    assert_eq!(results[1].reason, Some("InvalidCharacter".to_string()));
+
+    let pk = random_public_key();
+    let keys: Vec<String> = vec![Base16EncodedBytes::new(pk.as_slice()).into(), "0102".to_string()];
+    let results: Vec<DerivedAddress> =
+        addresses_from_public_keys(&JsValue::from_serde(&keys).unwrap(), Network::Mainnet)
+            .unwrap()
+            .into_serde()
+            .unwrap();
+    assert_eq!(results[0].address, Some(Address::from_public_key(&pk).unwrap().get_addr()));
+    assert!(results[1].error.is_some());
+}
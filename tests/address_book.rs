@@ -0,0 +1,64 @@
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+extern crate wasm_bindgen;
+
+use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use ergowallet_wasm::*;
+
+const ADDRESS: &str = "9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV";
+const MINERS_FEE_MAINNET_ADDRESS: &str =
+    "2iHkR7CWvD1R4j1yZg5bkeDRQavjAaVPeTDFGGLZduHyfWMuYpmhHocX8GJoaieTx78FntzJbCBVL6rf96ocJoZdmWBL2fci7NqWgAirppPQmZ7fN9V6z13Ay6brPriBKYqLp1bT2Fk4FkFLCfdPpe";
+
+#[wasm_bindgen_test]
+fn address_book_add_search_remove() {
+    let mut book = AddressBook::new();
+    book.add(ADDRESS, "Alice", Network::Mainnet).unwrap();
+    book.add(MINERS_FEE_MAINNET_ADDRESS, "Miners fee", Network::Mainnet).unwrap();
+    assert!(book.add(ADDRESS, "Alice", Network::Testnet).is_err());
+    assert!(book.add("9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9Uwptg", "Bob", Network::Mainnet).is_err());
+    assert_eq!(book.len(), 2);
+
+    let found: Vec<AddressBookEntry> = book.search("alice").unwrap().into_serde().unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].address, ADDRESS);
+    let found: Vec<AddressBookEntry> = book.search("2iHk").unwrap().into_serde().unwrap();
+    assert_eq!(found[0].label, "Miners fee");
+
+    assert!(book.remove(ADDRESS));
+    assert!(!book.remove(ADDRESS));
+    assert_eq!(book.label(ADDRESS), None);
+}
+
+#[wasm_bindgen_test]
+fn address_book_canonical_json() {
+    let mut book = AddressBook::new();
+    book.add(ADDRESS, "Alice", Network::Mainnet).unwrap();
+    book.add(MINERS_FEE_MAINNET_ADDRESS, "Miners fee", Network::Mainnet).unwrap();
+
+    let mut reversed = AddressBook::new();
+    reversed.add(MINERS_FEE_MAINNET_ADDRESS, "Miners fee", Network::Mainnet).unwrap();
+    reversed.add(ADDRESS, "Alice", Network::Mainnet).unwrap();
+
+    let json = book.to_json();
+    assert_eq!(json, reversed.to_json());
+    assert_eq!(AddressBook::from_json(&json).unwrap(), book);
+
+    let tampered = json.replace("9hzP24a2", "9hzP24a3");
+    assert!(AddressBook::from_json(&tampered).is_err());
+}
+
+#[wasm_bindgen_test]
+fn address_book_lookalike_addresses() {
+    let mut book = AddressBook::new();
+    book.add(ADDRESS, "Alice", Network::Mainnet).unwrap();
+
+    assert!(book.similar_entries(ADDRESS).is_empty());
+    assert!(book.similar_entries(MINERS_FEE_MAINNET_ADDRESS).is_empty());
+
+    let poisoned = format!("9hzP{}YBYV", "x".repeat(ADDRESS.len() - 8));
+    assert_eq!(book.similar_entries(&poisoned).len(), 1);
+    assert_eq!(book.similar_entries(&ADDRESS.replace("KLP", "KMP")).len(), 1);
+}
@@ -0,0 +1,165 @@
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+extern crate wasm_bindgen;
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use ergowallet_wasm::*;
+use ergo_lib::chain::{Base16DecodedBytes, Base16EncodedBytes, Digest32};
+use ergo_lib::chain::contract::Contract;
+use ergo_lib::chain::ergo_box::{BoxId, BoxValue, ErgoBoxCandidate, NonMandatoryRegisters};
+use ergo_lib::chain::transaction::UnsignedInput;
+use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use ergo_lib::ergotree_ir::address::{AddressEncoder, NetworkPrefix};
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+use std::convert::TryFrom;
+
+fn random_secret() -> (String, Vec<u8>) {
+    let dpi = DlogProverInput::random();
+    let secret: String = Base16EncodedBytes::new(dpi.w.to_bytes().as_slice()).into();
+    let pk = public_from_secret(&secret);
+    (secret, pk)
+}
+
+/// Transaction paying to a P2PK address, without the miner fee output whose contract
+/// ergo-lib 0.8 can't parse back from bytes
+fn unsigned_tx() -> UnsignedTransaction {
+    let box_id = Base16DecodedBytes::try_from(
+        "626925e6a7bb08e3b7cf73de2e71a98966e881e7fc0c54fbbc94b83c79de8c19".to_string(),
+    ).unwrap();
+    let address = AddressEncoder::new(NetworkPrefix::Mainnet)
+        .parse_address_from_str("9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV")
+        .unwrap();
+    let tx = ergo_lib::chain::transaction::unsigned::UnsignedTransaction::new(
+        vec![UnsignedInput {
+            box_id: BoxId(Digest32::try_from(box_id).unwrap()),
+            extension: ContextExtension::empty(),
+        }],
+        vec![],
+        vec![ErgoBoxCandidate {
+            value: BoxValue::SAFE_USER_MIN,
+            ergo_tree: Contract::pay_to_address(&address).unwrap().ergo_tree(),
+            tokens: vec![],
+            additional_registers: NonMandatoryRegisters::empty(),
+            creation_height: 0,
+        }],
+    );
+
+    UnsignedTransaction::from_json(&JsValue::from_serde(&tx).unwrap()).unwrap()
+}
+
+fn write_vlq(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reduced transaction with the single input reduced to proveDlog(pk)
+fn reduced_tx_bytes(tx: &UnsignedTransaction, pk: &[u8]) -> Vec<u8> {
+    let msg = tx.to_bytes();
+    let mut bytes = vec![];
+    write_vlq(&mut bytes, msg.len() as u64);
+    bytes.extend_from_slice(&msg);
+    bytes.push(0xcd);
+    bytes.extend_from_slice(pk);
+    write_vlq(&mut bytes, 1000);
+    write_vlq(&mut bytes, 1000);
+    bytes
+}
+
+fn reduced_tx(tx: &UnsignedTransaction, pk: &[u8]) -> String {
+    base64::encode_config(reduced_tx_bytes(tx, pk), base64::URL_SAFE_NO_PAD)
+}
+
+#[wasm_bindgen_test]
+fn static_ergo_pay_sign() {
+    let (secret, pk) = random_secret();
+    let tx = unsigned_tx();
+
+    let request = ErgoPayRequest::from_uri(&format!("ergopay:{}", reduced_tx(&tx, &pk))).unwrap();
+    assert_eq!(request.unsigned_tx().unwrap(), tx);
+    assert_eq!(request.severity(), MessageSeverity::None);
+
+    let signed = request.sign(vec![JsValue::from_str(&secret)].into_boxed_slice()).unwrap();
+    assert_eq!(signed.tx_id(), tx.tx_id());
+
+    // proof of the input is a signature of the transaction bytes
+    let json: serde_json::Value = signed.to_json().unwrap().into_serde().unwrap();
+    let proof = json["inputs"][0]["spendingProof"]["proofBytes"].as_str().unwrap().to_string();
+    let proof = Base16DecodedBytes::try_from(proof).unwrap().0;
+    let address = Address::from_public_key(&pk).unwrap().get_addr();
+    assert!(verify_signature(&address, &tx.to_bytes(), &proof).unwrap());
+
+    let (other_secret, _) = random_secret();
+    assert!(request.sign(vec![JsValue::from_str(&other_secret)].into_boxed_slice()).is_err());
+}
+
+#[wasm_bindgen_test]
+async fn dynamic_ergo_pay_request() {
+    let (_, pk) = random_secret();
+    let address = Address::from_public_key(&pk).unwrap().get_addr();
+    let fixture = format!(
+        "if (url !== 'https://dapp.example/pay/{}') throw new Error(url); \
+         return Promise.resolve({{reducedTx: '{}', message: 'Swap 1 ERG', messageSeverity: 'WARNING', replyTo: 'https://dapp.example/reply'}});",
+        address,
+        reduced_tx(&unsigned_tx(), &pk),
+    );
+    let fetch = js_sys::Function::new_with_args("url", &fixture);
+
+    let request = ergo_pay_request("ergopay://dapp.example/pay/#P2PK_ADDRESS#".to_string(), Some(address), fetch)
+        .await
+        .unwrap();
+    assert_eq!(request.message(), Some("Swap 1 ERG".to_string()));
+    assert_eq!(request.severity(), MessageSeverity::Warning);
+    assert_eq!(request.reply_to(), Some("https://dapp.example/reply".to_string()));
+    assert!(request.has_transaction());
+}
+
+#[wasm_bindgen_test]
+fn ergo_pay_error_message() {
+    let response = JsValue::from_str(r#"{"message": "Not enough funds", "messageSeverity": "ERROR"}"#);
+    let request = ErgoPayRequest::from_json(&response).unwrap();
+    assert_eq!(request.severity(), MessageSeverity::Error);
+    assert!(!request.has_transaction());
+    assert!(request.unsigned_tx().is_err());
+
+    assert!(ErgoPayRequest::from_uri("ergopay:not-a-transaction").is_err());
+}
+
+#[wasm_bindgen_test]
+fn malformed_reduced_transaction() {
+    let (_, pk) = random_secret();
+    let bytes = reduced_tx_bytes(&unsigned_tx(), &pk);
+    let from_bytes = |bytes: &[u8]| {
+        ErgoPayRequest::from_uri(&format!("ergopay:{}", base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)))
+    };
+    assert!(from_bytes(&bytes).is_ok());
+
+    // length of the transaction bytes does not fit into the address space
+    let mut huge_len = vec![0xff; 9];
+    huge_len.push(0x01);
+    assert!(from_bytes(&huge_len).is_err());
+
+    // total cost is missing
+    assert!(from_bytes(&bytes[..bytes.len() - 2]).is_err());
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(from_bytes(&trailing).is_err());
+}
+
+#[wasm_bindgen_test]
+async fn dynamic_ergo_pay_request_invalid_address() {
+    let fetch = js_sys::Function::new_with_args("url", "throw new Error('must not be called: ' + url);");
+    let result = ergo_pay_request(
+        "ergopay://dapp.example/pay/#P2PK_ADDRESS#".to_string(),
+        Some("9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV?amount=1".to_string()),
+        fetch,
+    ).await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,64 @@
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+extern crate wasm_bindgen;
+
+use wasm_bindgen_test::*;
+
+use ergowallet_wasm::*;
+use ergo_lib::chain::{Base16DecodedBytes, Base16EncodedBytes};
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+use std::convert::TryFrom;
+
+const MESSAGE: &str = "Login to ergowallet.io, nonce 42";
+
+fn random_secret() -> (String, String) {
+    let dpi = DlogProverInput::random();
+    let secret: String = Base16EncodedBytes::new(dpi.w.to_bytes().as_slice()).into();
+    let address = Address::from_public_key(&public_from_secret(&secret)).unwrap().get_addr();
+    (secret, address)
+}
+
+#[wasm_bindgen_test]
+fn sign_verify_message_success() {
+    let (secret, address) = random_secret();
+    let signature = sign_message(&secret, MESSAGE.as_bytes()).unwrap();
+    assert_eq!(signature.len(), 56);
+    assert!(verify_signature(&address, MESSAGE.as_bytes(), &signature).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn verify_message_tampered() {
+    let (secret, address) = random_secret();
+    let signature = sign_message(&secret, MESSAGE.as_bytes()).unwrap();
+    assert!(!verify_signature(&address, b"Another message", &signature).unwrap());
+
+    let (_, other_address) = random_secret();
+    assert!(!verify_signature(&other_address, MESSAGE.as_bytes(), &signature).unwrap());
+}
+
+/// Signature produced by sigmastate (the signer behind the node's `/utils/signMessage`),
+/// test vector of its `SigningSpecification`
+const NODE_ADDRESS: &str = "9i1ENzK3dp2y6WkC41AHvYz2FXTR1tKSFLzGku1MD9oRwx1pfmR";
+const NODE_MESSAGE: &str = "1dc01772ee0171f5f614c673e3c7fa1107a8cf727bdf5a6dadb379e93c0d1d00";
+const NODE_SIGNATURE: &str =
+    "bcb866ba434d5c77869ddcbc3f09ddd62dd2d2539bf99076674d1ae0c32338ea95581fdc18a3b66789904938ac641eba1a66d234070207a2";
+
+fn from_hex(s: &str) -> Vec<u8> {
+    Base16DecodedBytes::try_from(s.to_string()).unwrap().0
+}
+
+#[wasm_bindgen_test]
+fn verify_node_signature() {
+    let message = from_hex(NODE_MESSAGE);
+    let signature = from_hex(NODE_SIGNATURE);
+    assert!(verify_signature(NODE_ADDRESS, &message, &signature).unwrap());
+
+    let mut tampered = signature.clone();
+    tampered[0] ^= 1;
+    assert!(!verify_signature(NODE_ADDRESS, &message, &tampered).unwrap());
+
+    let mut tampered_message = message.clone();
+    tampered_message[0] ^= 1;
+    assert!(!verify_signature(NODE_ADDRESS, &tampered_message, &signature).unwrap());
+}
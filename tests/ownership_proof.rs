@@ -0,0 +1,58 @@
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+extern crate wasm_bindgen;
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use ergowallet_wasm::*;
+use ergo_lib::chain::Base16EncodedBytes;
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+
+const CHALLENGE: &str = "withdrawal-7f3a9c";
+
+fn random_secret() -> (String, String) {
+    let dpi = DlogProverInput::random();
+    let secret: String = Base16EncodedBytes::new(dpi.w.to_bytes().as_slice()).into();
+    let address = Address::from_public_key(&public_from_secret(&secret)).unwrap().get_addr();
+    (secret, address)
+}
+
+#[wasm_bindgen_test]
+fn ownership_proof_success() {
+    let (secret, address) = random_secret();
+    let proof = prove_address_ownership(&secret, &address, CHALLENGE).unwrap();
+
+    let parsed: OwnershipProof = proof.into_serde().unwrap();
+    assert_eq!(parsed.proof_type, "ergo-address-ownership");
+    assert_eq!(parsed.address, address);
+    assert_eq!(parsed.challenge, CHALLENGE);
+
+    assert!(verify_address_ownership(&proof, CHALLENGE, None).unwrap());
+    assert!(verify_address_ownership(&proof, CHALLENGE, Some(60_000.0)).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn ownership_proof_rejected() {
+    let (secret, address) = random_secret();
+    let (_, other_address) = random_secret();
+    assert!(prove_address_ownership(&secret, &other_address, CHALLENGE).is_err());
+
+    let proof = prove_address_ownership(&secret, &address, CHALLENGE).unwrap();
+    assert!(!verify_address_ownership(&proof, "another-challenge", None).unwrap());
+
+    let mut tampered: OwnershipProof = proof.into_serde().unwrap();
+    tampered.timestamp -= 1;
+    assert!(!verify_address_ownership(&JsValue::from_serde(&tampered).unwrap(), CHALLENGE, None).unwrap());
+
+    let mut tampered: OwnershipProof = proof.into_serde().unwrap();
+    tampered.address = other_address;
+    assert!(!verify_address_ownership(&JsValue::from_serde(&tampered).unwrap(), CHALLENGE, None).unwrap());
+
+    // expired
+    let mut old: OwnershipProof = proof.into_serde().unwrap();
+    old.timestamp -= 120_000;
+    assert!(!verify_address_ownership(&JsValue::from_serde(&old).unwrap(), CHALLENGE, Some(60_000.0)).unwrap());
+}
@@ -4,26 +4,218 @@ extern crate wasm_bindgen_test;
 extern crate wasm_bindgen;
 
 use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
 use ergowallet_wasm::*;
 
-const MESSAGE: &'static str = "Secret Message to Encrypt";
-const PASSWORD: &'static str = "Ergo Wallet Password!";
+const MESSAGE: &str = "Secret Message to Encrypt";
+const PASSWORD: &str = "Ergo Wallet Password!";
 const SALT: [u8; 32] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1];
 const NONCE: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1];
 
 #[wasm_bindgen_test]
 fn encrypt_decrypt_with_password_success() {
-    let encrypted = password_encrypt(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes()).unwrap();
+    let encrypted = password_encrypt_deterministic(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes(), &wasm_bindgen::JsValue::UNDEFINED).unwrap();
     let encrypted: Vec<u8> = encrypted.into_serde().unwrap();
-    let decrypted = password_decrypt(PASSWORD, &encrypted).unwrap();
-    let decrypted: Vec<u8> = decrypted.into_serde().unwrap();
+    let decrypted = password_decrypt(PASSWORD, &encrypted).unwrap().to_vec();
     assert_eq!(MESSAGE.as_bytes(), decrypted.as_slice());
 }
 
 #[wasm_bindgen_test]
 fn encrypt_decrypt_invalid_password() {
-    const INVALID_PASSWORD: &'static str = "Invalid Password";
-    let encrypted = password_encrypt(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes()).unwrap();
+    const INVALID_PASSWORD: &str = "Invalid Password";
+    let encrypted = password_encrypt_deterministic(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes(), &wasm_bindgen::JsValue::UNDEFINED).unwrap();
     let encrypted: Vec<u8> = encrypted.into_serde().unwrap();
     assert!(password_decrypt(INVALID_PASSWORD, &encrypted).is_err());
-}
\ No newline at end of file
+}
+
+#[wasm_bindgen_test]
+fn decrypt_legacy_headerless_format() {
+    let encrypted = password_encrypt_deterministic(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes(), &wasm_bindgen::JsValue::UNDEFINED).unwrap();
+    let encrypted: Vec<u8> = encrypted.into_serde().unwrap();
+
+    // legacy format written before the envelope: salt || nonce || ciphertext (with 16 bytes tag),
+    // with the same PBKDF2 rounds and cipher as the default envelope
+    let ciphertext = &encrypted[encrypted.len() - MESSAGE.len() - 16..];
+    let mut legacy = vec![];
+    legacy.extend_from_slice(&SALT);
+    legacy.extend_from_slice(&NONCE);
+    legacy.extend_from_slice(ciphertext);
+
+    let decrypted = password_decrypt(PASSWORD, &legacy).unwrap().to_vec();
+    assert_eq!(MESSAGE.as_bytes(), decrypted.as_slice());
+}
+
+#[wasm_bindgen_test]
+fn encrypt_produces_versioned_envelope() {
+    let encrypted = password_encrypt_deterministic(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes(), &wasm_bindgen::JsValue::UNDEFINED).unwrap();
+    let encrypted: Vec<u8> = encrypted.into_serde().unwrap();
+    // magic, version 1, PBKDF2-HMAC-SHA512 with 10 000 rounds
+    assert_eq!(&encrypted[..10], &[0x45, 0x57, 0x53, 0x45, 1, 1, 0, 0, 0x27, 0x10]);
+}
+
+#[wasm_bindgen_test]
+fn encrypt_decrypt_with_memory_hard_kdf() {
+    let scrypt: serde_json::Value = serde_json::json!({"kdf": "scrypt", "logN": 10, "r": 8, "p": 1});
+    let argon2id: serde_json::Value = serde_json::json!({"kdf": "argon2id", "memoryKib": 1024, "iterations": 2, "parallelism": 1});
+
+    for kdf in [scrypt, argon2id].iter() {
+        let kdf = wasm_bindgen::JsValue::from_serde(kdf).unwrap();
+        let encrypted = password_encrypt_deterministic(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes(), &kdf).unwrap();
+        let encrypted: Vec<u8> = encrypted.into_serde().unwrap();
+        let decrypted: Vec<u8> = password_decrypt(PASSWORD, &encrypted).unwrap().to_vec();
+        assert_eq!(MESSAGE.as_bytes(), decrypted.as_slice());
+        assert!(password_decrypt("Invalid Password", &encrypted).is_err());
+    }
+}
+
+#[wasm_bindgen_test]
+fn node_secret_export_import() {
+    const IV: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5];
+    let exported = export_node_secret_deterministic(PASSWORD, &SALT, &IV, MESSAGE.as_bytes()).unwrap();
+
+    let secret: NodeEncryptedSecret = exported.into_serde().unwrap();
+    assert_eq!(secret.cipher_params.prf, "HmacSHA256");
+    assert_eq!(secret.auth_tag.len(), 32);
+
+    let imported: Vec<u8> = import_node_secret(PASSWORD, &exported).unwrap().to_vec();
+    assert_eq!(MESSAGE.as_bytes(), imported.as_slice());
+    assert!(import_node_secret("Invalid Password", &exported).is_err());
+}
+
+/// secret.json in the node format (PBKDF2WithHmacSHA256, 128000 iterations,
+/// AES/GCM/NoPadding with 16 bytes IV), produced outside of this crate
+const NODE_SECRET_JSON: &str = r#"{
+    "cipherText": "d9d0cb81872b00df5b2912aed6cdd27a04c1a1b0c68781f4570907726b7b39d7",
+    "salt": "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+    "iv": "6465666768696a6b6c6d6e6f70717273",
+    "authTag": "8f0a2a46d2f6985127d3aa028cd67b0c",
+    "cipherParams": {"prf": "HmacSHA256", "c": 128000, "dkLen": 256}
+}"#;
+const NODE_SECRET_SEED: &str = "e2c5c3f1a0d4b6987f3e2d1c0b9a8877665544332211ffeeddccbbaa99887766";
+
+#[wasm_bindgen_test]
+fn node_secret_import_fixture() {
+    let secret: serde_json::Value = serde_json::from_str(NODE_SECRET_JSON).unwrap();
+    let json = wasm_bindgen::JsValue::from_serde(&secret).unwrap();
+
+    let imported: Vec<u8> = import_node_secret(PASSWORD, &json).unwrap().to_vec();
+    let seed: String = ergo_lib::chain::Base16EncodedBytes::new(imported.as_slice()).into();
+    assert_eq!(seed, NODE_SECRET_SEED);
+
+    let mut expensive = secret.clone();
+    expensive["cipherParams"]["c"] = serde_json::json!(u32::MAX);
+    let expensive = wasm_bindgen::JsValue::from_serde(&expensive).unwrap();
+    assert!(import_node_secret(PASSWORD, &expensive).is_err());
+}
+
+#[wasm_bindgen_test]
+fn encrypt_with_random_salt_and_nonce() {
+    let undefined = wasm_bindgen::JsValue::UNDEFINED;
+    let first: Vec<u8> = password_encrypt(PASSWORD, MESSAGE.as_bytes(), &undefined).unwrap().into_serde().unwrap();
+    let second: Vec<u8> = password_encrypt(PASSWORD, MESSAGE.as_bytes(), &undefined).unwrap().into_serde().unwrap();
+    assert_ne!(first, second);
+
+    let decrypted: Vec<u8> = password_decrypt(PASSWORD, &first).unwrap().to_vec();
+    assert_eq!(MESSAGE.as_bytes(), decrypted.as_slice());
+}
+
+#[wasm_bindgen_test]
+fn encrypt_invalid_nonce_size() {
+    assert!(password_encrypt_deterministic(PASSWORD, &SALT, &NONCE[..8], MESSAGE.as_bytes(), &wasm_bindgen::JsValue::UNDEFINED).is_err());
+    assert!(password_encrypt_deterministic(PASSWORD, &SALT[..16], &NONCE, MESSAGE.as_bytes(), &wasm_bindgen::JsValue::UNDEFINED).is_err());
+}
+
+#[wasm_bindgen_test]
+fn encrypt_rejects_unsupported_kdf_cost() {
+    let kdfs = [
+        serde_json::json!({"kdf": "pbkdf2-sha512", "rounds": 0}),
+        serde_json::json!({"kdf": "pbkdf2-sha512", "rounds": 10_000_001}),
+        serde_json::json!({"kdf": "scrypt", "logN": 0, "r": 8, "p": 1}),
+        serde_json::json!({"kdf": "scrypt", "logN": 21, "r": 8, "p": 1}),
+        serde_json::json!({"kdf": "argon2id", "memoryKib": 0, "iterations": 2, "parallelism": 1}),
+        serde_json::json!({"kdf": "argon2id", "memoryKib": 1024, "iterations": 0, "parallelism": 1}),
+    ];
+    for kdf in kdfs.iter() {
+        let kdf = wasm_bindgen::JsValue::from_serde(kdf).unwrap();
+        assert!(password_encrypt(PASSWORD, MESSAGE.as_bytes(), &kdf).is_err());
+    }
+}
+
+#[wasm_bindgen_test]
+fn encrypt_decrypt_with_calibrated_maximum() {
+    // unreachable target: calibration stops at the maximum supported cost
+    let kdf = calibrate_kdf("pbkdf2-sha512", f64::INFINITY).unwrap();
+    let params: serde_json::Value = kdf.into_serde().unwrap();
+    assert_eq!(params["rounds"], 10_000_000);
+
+    let encrypted: Vec<u8> = password_encrypt(PASSWORD, MESSAGE.as_bytes(), &kdf).unwrap().into_serde().unwrap();
+    let decrypted: Vec<u8> = password_decrypt(PASSWORD, &encrypted).unwrap().to_vec();
+    assert_eq!(MESSAGE.as_bytes(), decrypted.as_slice());
+}
+
+#[wasm_bindgen_test]
+fn decrypt_error_categories() {
+    use wasm_bindgen::JsCast;
+
+    fn error_name(err: wasm_bindgen::JsValue) -> String {
+        err.dyn_into::<js_sys::Error>().unwrap().name().into()
+    }
+
+    let encrypted: Vec<u8> = password_encrypt_deterministic(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes(), &wasm_bindgen::JsValue::UNDEFINED).unwrap().into_serde().unwrap();
+
+    assert_eq!(error_name(password_decrypt(PASSWORD, &encrypted[..20]).unwrap_err()), "TruncatedInput");
+    assert_eq!(error_name(password_decrypt(PASSWORD, &SALT).unwrap_err()), "TruncatedInput");
+    assert_eq!(error_name(password_decrypt("Invalid Password", &encrypted).unwrap_err()), "AuthenticationFailed");
+
+    let mut corrupted = encrypted.clone();
+    *corrupted.last_mut().unwrap() ^= 1;
+    assert_eq!(error_name(password_decrypt(PASSWORD, &corrupted).unwrap_err()), "AuthenticationFailed");
+
+    let mut future = encrypted.clone();
+    future[4] = 99;
+    assert_eq!(error_name(password_decrypt(PASSWORD, &future).unwrap_err()), "UnsupportedVersion");
+
+    // PBKDF2 rounds in the header (after magic, version and kdf id) set to u32::MAX
+    let mut expensive = encrypted.clone();
+    expensive[6..10].copy_from_slice(&[0xff; 4]);
+    assert_eq!(error_name(password_decrypt(PASSWORD, &expensive).unwrap_err()), "InvalidParameters");
+}
+
+#[wasm_bindgen_test]
+fn change_password() {
+    const NEW_PASSWORD: &str = "New Ergo Wallet Password!";
+    let encrypted: Vec<u8> = password_encrypt_deterministic(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes(), &wasm_bindgen::JsValue::UNDEFINED).unwrap().into_serde().unwrap();
+
+    let kdf = wasm_bindgen::JsValue::from_serde(&serde_json::json!({"kdf": "scrypt", "logN": 10, "r": 8, "p": 1})).unwrap();
+    let changed: Vec<u8> = password_change(&encrypted, PASSWORD, NEW_PASSWORD, &kdf, None).unwrap().into_serde().unwrap();
+
+    assert!(password_decrypt(PASSWORD, &changed).is_err());
+    let decrypted: Vec<u8> = password_decrypt(NEW_PASSWORD, &changed).unwrap().to_vec();
+    assert_eq!(MESSAGE.as_bytes(), decrypted.as_slice());
+
+    assert!(password_change(&encrypted, "Invalid Password", NEW_PASSWORD, &wasm_bindgen::JsValue::UNDEFINED, None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn encrypt_decrypt_with_associated_data() {
+    let undefined = wasm_bindgen::JsValue::UNDEFINED;
+    let encrypted: Vec<u8> = password_encrypt_with_aad(PASSWORD, MESSAGE.as_bytes(), b"wallet-1", &undefined)
+        .unwrap().into_serde().unwrap();
+
+    let decrypted: Vec<u8> = password_decrypt_with_aad(PASSWORD, &encrypted, b"wallet-1").unwrap().to_vec();
+    assert_eq!(MESSAGE.as_bytes(), decrypted.as_slice());
+
+    assert!(password_decrypt_with_aad(PASSWORD, &encrypted, b"wallet-2").is_err());
+    assert!(password_decrypt(PASSWORD, &encrypted).is_err());
+}
+
+#[wasm_bindgen_test]
+fn decrypt_into_clearable_bytes() {
+    let encrypted: Vec<u8> = password_encrypt_deterministic(PASSWORD, &SALT, &NONCE, MESSAGE.as_bytes(), &wasm_bindgen::JsValue::UNDEFINED).unwrap().into_serde().unwrap();
+
+    let decrypted = password_decrypt(PASSWORD, &encrypted).unwrap();
+    assert_eq!(MESSAGE.as_bytes(), decrypted.to_vec().as_slice());
+
+    decrypted.fill(0, 0, decrypted.length());
+    assert!(decrypted.to_vec().iter().all(|b| *b == 0));
+}
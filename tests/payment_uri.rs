@@ -0,0 +1,85 @@
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+extern crate wasm_bindgen;
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use ergowallet_wasm::*;
+
+const ADDRESS: &str = "9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV";
+const TOKEN_ID: &str = "626925e6a7bb08e3b7cf73de2e71a98966e881e7fc0c54fbbc94b83c79de8c19";
+
+fn parse(uri: &str) -> Result<TxOutput, String> {
+    parse_payment_uri(uri, Network::Mainnet)
+        .map(|v| v.into_serde().unwrap())
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[wasm_bindgen_test]
+fn parse_create_payment_uri() {
+    let uri = format!("ergo:{}?amount=1.5&token-{}=10&r4=0402", ADDRESS, TOKEN_ID);
+    let output = parse(&uri).unwrap();
+    assert_eq!(output.address, ADDRESS);
+    assert_eq!(output.value, "1500000000");
+    assert_eq!(output.assets.len(), 1);
+    assert_eq!(output.assets[0].token_id, TOKEN_ID);
+    assert_eq!(output.assets[0].amount, "10");
+    assert_eq!(output.registers.get("R4"), Some(&"0402".to_string()));
+
+    let generated = create_payment_uri(&JsValue::from_serde(&output).unwrap(), Network::Mainnet).unwrap();
+    assert_eq!(generated, uri);
+
+    let address_only = parse(&format!("ergo:{}", ADDRESS)).unwrap();
+    assert_eq!(address_only.value, "0");
+}
+
+#[wasm_bindgen_test]
+fn parse_invalid_payment_uri() {
+    assert!(parse(&format!("bitcoin:{}", ADDRESS)).is_err());
+    // multibyte characters around the scheme boundary
+    assert!(parse("erγé…").is_err());
+    assert!(parse("ergoé").is_err());
+    assert!(parse("ergo:γ").is_err());
+    assert!(parse(&format!("ergo:{}?amount=1.0000000001", ADDRESS)).is_err());
+    assert!(parse(&format!("ergo:{}?amount=1&amount=2", ADDRESS)).is_err());
+    assert!(parse(&format!("ergo:{}?token-abc=1", ADDRESS)).is_err());
+    assert!(parse(&format!("ergo:{}?token-{}=0", ADDRESS, TOKEN_ID)).is_err());
+    // registers must start at R4
+    assert!(parse(&format!("ergo:{}?r5=0402", ADDRESS)).is_err());
+    assert!(parse_payment_uri(&format!("ergo:{}", ADDRESS), Network::Testnet).is_err());
+}
+
+#[wasm_bindgen_test]
+fn create_payment_uri_checks_tokens() {
+    let create = |assets: Vec<AssetValue>| {
+        let output = TxOutput {
+            value: "1000000000".to_string(),
+            address: ADDRESS.to_string(),
+            assets,
+            registers: Default::default(),
+        };
+        create_payment_uri(&JsValue::from_serde(&output).unwrap(), Network::Mainnet)
+    };
+    let token = |token_id: &str, amount: &str| AssetValue {
+        token_id: token_id.to_string(),
+        amount: amount.to_string(),
+    };
+
+    // generated URI parses back to the same output
+    let uri = create(vec![token(&TOKEN_ID.to_ascii_uppercase(), "5")]).unwrap();
+    let output = parse(&uri).unwrap();
+    assert_eq!(output.value, "1000000000");
+    assert_eq!(output.assets.len(), 1);
+    assert_eq!(output.assets[0].token_id, TOKEN_ID);
+    assert_eq!(output.assets[0].amount, "5");
+
+    // tokens the parser rejects are not put into the URI
+    let injected = format!("{}=1&amount", TOKEN_ID);
+    assert!(create(vec![token(&injected, "1")]).is_err());
+    assert!(create(vec![token("abc", "1")]).is_err());
+    assert!(create(vec![token(TOKEN_ID, "0")]).is_err());
+    assert!(create(vec![token(TOKEN_ID, "1"), token(&TOKEN_ID.to_ascii_uppercase(), "2")]).is_err());
+}
@@ -0,0 +1,78 @@
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+extern crate wasm_bindgen;
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use ergowallet_wasm::*;
+use ergo_lib::chain::Base16EncodedBytes;
+use ergo_lib::chain::ergo_box::{BoxValue, ErgoBox};
+use ergo_lib::chain::transaction::TxId;
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+use ergo_lib::ergotree_ir::address::{Address as ErgoAddress, AddressEncoder, NetworkPrefix};
+
+fn random_secret() -> String {
+    let dpi = DlogProverInput::random();
+    Base16EncodedBytes::new(dpi.w.to_bytes().as_slice()).into()
+}
+
+/// Box created by a transaction paying to the output
+fn box_for_output(output: &TxOutput) -> JsValue {
+    let inputs = vec![JsValue::from_serde(&TxInput {
+        box_id: "626925e6a7bb08e3b7cf73de2e71a98966e881e7fc0c54fbbc94b83c79de8c19".to_string(),
+    }).unwrap()].into_boxed_slice();
+    let outputs = vec![JsValue::from_serde(output).unwrap()].into_boxed_slice();
+    let tx = Transaction::create(inputs, outputs, u64::from(BoxValue::SAFE_USER_MIN), 0).unwrap();
+
+    let tx: ergo_lib::chain::transaction::unsigned::UnsignedTransaction =
+        tx.to_json().unwrap().into_serde().unwrap();
+    let ergo_box = ErgoBox::from_box_candidate(&tx.output_candidates[0], TxId::zero(), 0);
+    JsValue::from_serde(&ergo_box).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn stealth_output_scan() {
+    let secret = random_secret();
+    let meta_address = stealth_meta_address(&secret).unwrap();
+    let value = u64::from(BoxValue::SAFE_USER_MIN).to_string();
+
+    let output: TxOutput = create_stealth_output(&meta_address, &value, Network::Mainnet)
+        .unwrap()
+        .into_serde()
+        .unwrap();
+    let address = Address::from_base58(&output.address).unwrap();
+    assert_eq!(address.address_type(), AddressType::P2PK);
+    assert_ne!(output.address, meta_address);
+    // one-time address is an ordinary P2PK address for ergo-lib (and the node)
+    let parsed = AddressEncoder::new(NetworkPrefix::Mainnet)
+        .parse_address_from_str(&output.address)
+        .unwrap();
+    assert!(matches!(parsed, ErgoAddress::P2PK(_)));
+    assert!(output.registers.contains_key("R4"));
+
+    // outputs for the same meta-address are unlinkable
+    let other: TxOutput = create_stealth_output(&meta_address, &value, Network::Mainnet)
+        .unwrap()
+        .into_serde()
+        .unwrap();
+    assert_ne!(output.address, other.address);
+
+    let stealth_box = box_for_output(&output);
+    let box_id: String = Base16EncodedBytes::new(&stealth_box.into_serde::<ErgoBox>().unwrap().box_id().0 .0[..]).into();
+    let boxes = vec![stealth_box.clone()].into_boxed_slice();
+    let found: Vec<String> = scan_stealth_boxes(&secret, boxes.clone()).unwrap().into_serde().unwrap();
+    assert_eq!(found, vec![box_id]);
+
+    // one-time secret is the key of the one-time address
+    let one_time_secret = stealth_box_secret(&secret, &stealth_box).unwrap().to_vec();
+    let one_time_secret: String = Base16EncodedBytes::new(one_time_secret.as_slice()).into();
+    assert_eq!(address.public_key(), Some(public_from_secret(&one_time_secret)));
+
+    let other_secret = random_secret();
+    let found: Vec<String> = scan_stealth_boxes(&other_secret, boxes).unwrap().into_serde().unwrap();
+    assert!(found.is_empty());
+    assert!(stealth_box_secret(&other_secret, &stealth_box).is_err());
+}
@@ -5,18 +5,25 @@ extern crate wasm_bindgen;
 
 use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
 use web_sys::console;
 
 use ergowallet_wasm::*;
 use ergo_lib::wallet::secret_key::SecretKey;
-use ergo_lib::chain::Base16EncodedBytes;
-use ergo_lib::chain::ergo_box::{ErgoBox, ErgoBoxCandidate};
+use ergo_lib::chain::{Base16DecodedBytes, Base16EncodedBytes, Digest32};
+use ergo_lib::chain::token::{Token, TokenAmount, TokenId};
+use ergo_lib::chain::ergo_box::{BoxId, ErgoBox, ErgoBoxCandidate};
 use ergo_lib::chain::ergo_box::BoxValue;
 use ergo_lib::chain::contract::Contract;
 use ergo_lib::chain::ergo_box::NonMandatoryRegisters;
 use ergo_lib::chain::transaction::{TxId, UnsignedInput};
+use ergo_lib::ergotree_ir::address::{Address as ErgoAddress, AddressEncoder, NetworkPrefix};
 use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
 use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use std::convert::TryFrom;
+
+const MINERS_FEE_MAINNET_ADDRESS: &str =
+    "2iHkR7CWvD1R4j1yZg5bkeDRQavjAaVPeTDFGGLZduHyfWMuYpmhHocX8GJoaieTx78FntzJbCBVL6rf96ocJoZdmWBL2fci7NqWgAirppPQmZ7fN9V6z13Ay6brPriBKYqLp1bT2Fk4FkFLCfdPpe";
 
 
 #[wasm_bindgen_test]
@@ -24,14 +31,14 @@ fn tx_sign_success() {
     // 1. Create random secret key
     let dpi = DlogProverInput::random();
     let secret1 = dpi.w.to_bytes();
-    let secretKey = SecretKey::DlogSecretKey(dpi);
+    let secret_key = SecretKey::DlogSecretKey(dpi);
     let s: String = Base16EncodedBytes::new(secret1.as_slice()).into();
 
     console::log_1(&JsValue::from(&s));
     let js_secrets = [JsValue::from_serde(&s).unwrap()]
         .to_vec()
         .into_boxed_slice();
-    let address = secretKey.get_address_from_public_image();
+    let address = secret_key.get_address_from_public_image();
 
     // 2. Create available boxes
     let boxes = vec![
@@ -80,7 +87,7 @@ fn tx_sign_success() {
         js_secrets,
         js_boxes,
         &JsValue::from_serde(&tx).unwrap(),
-    );
+    ).unwrap();
 }
 
 #[wasm_bindgen_test]
@@ -95,6 +102,7 @@ pub fn tx_creation() {
     ];
 
     let outputs: Vec<TxOutput> = vec![TxOutput {
+        registers: Default::default(),
         assets: vec![AssetValue {
             token_id: "626925e6a7bb08e3b7cf73de2e71a98966e881e7fc0c54fbbc94b83c79de8c19".to_string(),
             amount: "1".to_string()
@@ -116,7 +124,333 @@ pub fn tx_creation() {
         .into_boxed_slice();
 
     let result = Transaction::create(
-        js_value, js_outputs, BoxValue::SAFE_USER_MIN.as_u64().clone(), 0).unwrap();
+        js_value, js_outputs, u64::from(BoxValue::SAFE_USER_MIN), 0).unwrap();
 
     console::log_1(&result.to_json().unwrap());
 }
+
+#[wasm_bindgen_test]
+pub fn tx_creation_invalid_registers() {
+    let inputs = vec![JsValue::from_serde(&TxInput {
+        box_id: "626925e6a7bb08e3b7cf73de2e71a98966e881e7fc0c54fbbc94b83c79de8c19".to_string(),
+    }).unwrap()].into_boxed_slice();
+
+    for (id, value) in [("R4", "not hex"), ("R4", "ff"), ("R5", "0402")].iter() {
+        let mut registers = std::collections::BTreeMap::new();
+        registers.insert(id.to_string(), value.to_string());
+        let outputs = vec![JsValue::from_serde(&TxOutput {
+            registers,
+            assets: vec![],
+            value: u64::from(BoxValue::SAFE_USER_MIN).to_string(),
+            address: "9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV".to_string(),
+        }).unwrap()].into_boxed_slice();
+
+        let result = Transaction::create(inputs.clone(), outputs, u64::from(BoxValue::SAFE_USER_MIN), 0);
+        assert!(result.is_err());
+    }
+}
+
+/// Pays `SAFE_USER_MIN` to `address` without a miner fee output: ergo-lib 0.8 cannot
+/// parse the fee contract back from bytes
+fn unsigned_tx_without_fee(box_id: BoxId, address: &ErgoAddress) -> UnsignedTransaction {
+    let tx = ergo_lib::chain::transaction::unsigned::UnsignedTransaction::new(
+        vec![UnsignedInput {
+            box_id,
+            extension: ContextExtension::empty(),
+        }],
+        vec![],
+        vec![ErgoBoxCandidate {
+            value: BoxValue::SAFE_USER_MIN,
+            ergo_tree: Contract::pay_to_address(address).unwrap().ergo_tree(),
+            tokens: vec![],
+            additional_registers: NonMandatoryRegisters::empty(),
+            creation_height: 0,
+        }],
+    );
+
+    UnsignedTransaction::from_json(&JsValue::from_serde(&tx).unwrap()).unwrap()
+}
+
+#[wasm_bindgen_test]
+pub fn unsigned_tx_bytes_roundtrip() {
+    let box_id = Base16DecodedBytes::try_from(
+        "626925e6a7bb08e3b7cf73de2e71a98966e881e7fc0c54fbbc94b83c79de8c19".to_string(),
+    ).unwrap();
+    let address = AddressEncoder::new(NetworkPrefix::Mainnet)
+        .parse_address_from_str("9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV")
+        .unwrap();
+
+    let tx = unsigned_tx_without_fee(BoxId(Digest32::try_from(box_id).unwrap()), &address);
+
+    let parsed = UnsignedTransaction::from_bytes(&tx.to_bytes()).unwrap();
+    assert_eq!(tx, parsed);
+    assert_eq!(tx.tx_id(), parsed.tx_id());
+
+    let from_json = UnsignedTransaction::from_json(&tx.to_json().unwrap()).unwrap();
+    assert_eq!(tx, from_json);
+}
+
+#[wasm_bindgen_test]
+pub fn signed_tx_bytes_roundtrip() {
+    let dpi = DlogProverInput::random();
+    let secret: String = Base16EncodedBytes::new(dpi.w.to_bytes().as_slice()).into();
+    let address = SecretKey::DlogSecretKey(dpi).get_address_from_public_image();
+    let min = u64::from(BoxValue::SAFE_USER_MIN);
+
+    let input = ErgoBox::from_box_candidate(
+        &ErgoBoxCandidate {
+            value: BoxValue::new(min * 2).unwrap(),
+            ergo_tree: Contract::pay_to_address(&address).unwrap().ergo_tree(),
+            tokens: vec![],
+            additional_registers: NonMandatoryRegisters::empty(),
+            creation_height: 0,
+        },
+        TxId::zero(), 0);
+
+    let unsigned = unsigned_tx_without_fee(input.box_id(), &address);
+    assert_eq!(UnsignedTransaction::from_bytes(&unsigned.to_bytes()).unwrap(), unsigned);
+
+    let signed = Transaction::sign(
+        vec![JsValue::from_serde(&secret).unwrap()].into_boxed_slice(),
+        vec![JsValue::from_serde(&input).unwrap()].into_boxed_slice(),
+        &unsigned.to_json().unwrap(),
+    ).unwrap();
+
+    let bytes = signed.to_bytes();
+    let parsed = Transaction::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.tx_id(), signed.tx_id());
+    assert_eq!(parsed.to_bytes(), bytes);
+
+    // inputs with proofs are not accepted as an unsigned transaction
+    assert!(UnsignedTransaction::from_bytes(&bytes).is_err());
+}
+
+#[wasm_bindgen_test]
+pub fn tx_summary() {
+    let dpi = DlogProverInput::random();
+    let address = SecretKey::DlogSecretKey(dpi).get_address_from_public_image();
+    let wallet_address = AddressEncoder::new(NetworkPrefix::Mainnet).address_to_str(&address);
+
+    let input = ErgoBox::from_box_candidate(
+        &ErgoBoxCandidate {
+            value: BoxValue::new(u64::from(BoxValue::SAFE_USER_MIN) * 2).unwrap(),
+            ergo_tree: Contract::pay_to_address(&address).unwrap().ergo_tree(),
+            tokens: vec![],
+            additional_registers: NonMandatoryRegisters::empty(),
+            creation_height: 0,
+        },
+        TxId::zero(), 0);
+
+    let js_inputs = vec![JsValue::from_serde(&TxInput {
+        box_id: Base16EncodedBytes::new(&input.box_id().0 .0[..]).into(),
+    }).unwrap()].into_boxed_slice();
+    let js_outputs = vec![JsValue::from_serde(&TxOutput {
+        registers: Default::default(),
+        assets: vec![],
+        value: u64::from(BoxValue::SAFE_USER_MIN).to_string(),
+        address: "9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV".to_string(),
+    }).unwrap()].into_boxed_slice();
+
+    let tx = Transaction::create(js_inputs, js_outputs, u64::from(BoxValue::SAFE_USER_MIN), 0).unwrap();
+
+    let summary: TxSummary = summarize_transaction(
+        &tx,
+        vec![JsValue::from_serde(&input).unwrap()].into_boxed_slice(),
+        vec![JsValue::from_serde(&wallet_address).unwrap()].into_boxed_slice(),
+    ).unwrap().into_serde().unwrap();
+
+    let min = u64::from(BoxValue::SAFE_USER_MIN) as i64;
+    assert_eq!(summary.erg_change, (-2 * min).to_string());
+    assert_eq!(summary.fee, min.to_string());
+    assert_eq!(summary.recipients.len(), 1);
+    assert_eq!(summary.recipients[0].address, "9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV");
+    assert!(summary.unknown_script_outputs.is_empty());
+}
+
+#[wasm_bindgen_test]
+pub fn tx_summary_fee_tokens_and_wallet_changes() {
+    let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
+    let spender = SecretKey::DlogSecretKey(DlogProverInput::random()).get_address_from_public_image();
+    let change = SecretKey::DlogSecretKey(DlogProverInput::random()).get_address_from_public_image();
+    let fee_address = encoder.parse_address_from_str(MINERS_FEE_MAINNET_ADDRESS).unwrap();
+    let min = u64::from(BoxValue::SAFE_USER_MIN);
+
+    let token_id = "11".repeat(32);
+    let token = |amount: u64| Token {
+        token_id: TokenId(Digest32::try_from(Base16DecodedBytes::try_from(token_id.clone()).unwrap()).unwrap()),
+        amount: TokenAmount::try_from(amount).unwrap(),
+    };
+    let candidate = |address: &ErgoAddress, value: u64, tokens: Vec<Token>| ErgoBoxCandidate {
+        value: BoxValue::new(value).unwrap(),
+        ergo_tree: Contract::pay_to_address(address).unwrap().ergo_tree(),
+        tokens,
+        additional_registers: NonMandatoryRegisters::empty(),
+        creation_height: 0,
+    };
+
+    let input = ErgoBox::from_box_candidate(&candidate(&spender, min * 4, vec![token(10)]), TxId::zero(), 0);
+    let tx = ergo_lib::chain::transaction::unsigned::UnsignedTransaction::new(
+        vec![UnsignedInput {
+            box_id: input.box_id(),
+            extension: ContextExtension::empty(),
+        }],
+        vec![],
+        vec![
+            candidate(&change, min * 3, vec![token(7)]),
+            candidate(&fee_address, min, vec![token(3)]),
+        ],
+    );
+    let tx = UnsignedTransaction::from_json(&JsValue::from_serde(&tx).unwrap()).unwrap();
+
+    let spender = encoder.address_to_str(&spender);
+    let change = encoder.address_to_str(&change);
+    let summary: TxSummary = summarize_transaction(
+        &tx,
+        vec![JsValue::from_serde(&input).unwrap()].into_boxed_slice(),
+        vec![JsValue::from_serde(&spender).unwrap(), JsValue::from_serde(&change).unwrap()].into_boxed_slice(),
+    ).unwrap().into_serde().unwrap();
+
+    // tokens given to the miner are neither burned nor lost
+    assert!(summary.burned.is_empty());
+    assert_eq!(summary.fee, min.to_string());
+    assert_eq!(summary.fee_assets.len(), 1);
+    assert_eq!(summary.fee_assets[0].amount, "3");
+    assert!(summary.recipients.is_empty());
+
+    assert_eq!(summary.erg_change, (-(min as i64)).to_string());
+    assert_eq!(summary.token_changes[0].amount, "-3");
+
+    assert_eq!(summary.wallet_changes.len(), 2);
+    assert_eq!(summary.wallet_changes[0].address, spender);
+    assert_eq!(summary.wallet_changes[0].erg_change, (-4 * min as i64).to_string());
+    assert_eq!(summary.wallet_changes[0].token_changes[0].amount, "-10");
+    assert_eq!(summary.wallet_changes[1].address, change);
+    assert_eq!(summary.wallet_changes[1].erg_change, (3 * min).to_string());
+    assert_eq!(summary.wallet_changes[1].token_changes[0].amount, "7");
+}
+
+#[wasm_bindgen_test]
+pub fn ergo_box_json_and_id() {
+    let candidate = ErgoBoxCandidate {
+        value: BoxValue::SAFE_USER_MIN,
+        ergo_tree: Contract::pay_to_address(
+            &SecretKey::DlogSecretKey(DlogProverInput::random()).get_address_from_public_image(),
+        ).unwrap().ergo_tree(),
+        tokens: vec![],
+        additional_registers: NonMandatoryRegisters::empty(),
+        creation_height: 100,
+    };
+    let expected = ErgoBox::from_box_candidate(&candidate, TxId::zero(), 1);
+    let expected_id: String = Base16EncodedBytes::new(&expected.box_id().0 .0[..]).into();
+
+    // candidate JSON is the box JSON without the id and the position in the transaction
+    let mut candidate_json = serde_json::to_value(&expected).unwrap();
+    for field in &["boxId", "transactionId", "index"] {
+        candidate_json.as_object_mut().unwrap().remove(*field);
+    }
+    let js_candidate = ergowallet_wasm::ErgoBoxCandidate::from_json(&JsValue::from_serde(&candidate_json).unwrap()).unwrap();
+    assert_eq!(js_candidate.value(), u64::from(BoxValue::SAFE_USER_MIN));
+    assert_eq!(js_candidate, ergowallet_wasm::ErgoBoxCandidate::from(candidate));
+    assert_eq!(js_candidate.to_json().unwrap().into_serde::<serde_json::Value>().unwrap(), candidate_json);
+    let tx_id: String = Base16EncodedBytes::new(&TxId::zero().0 .0[..]).into();
+    assert_eq!(js_candidate.box_id(&tx_id, 1).unwrap(), expected_id);
+
+    let js_box = ergowallet_wasm::ErgoBox::from_json(&JsValue::from_serde(&expected).unwrap()).unwrap();
+    assert_eq!(js_box.box_id(), expected_id);
+    assert_eq!(js_box.index(), 1);
+    assert_eq!(js_box.creation_height(), 100);
+    let roundtrip = ergowallet_wasm::ErgoBox::from_json(&js_box.to_json().unwrap()).unwrap();
+    assert_eq!(js_box, roundtrip);
+}
+
+#[wasm_bindgen_test]
+pub fn chained_tx_sign_success() {
+    let dpi = DlogProverInput::random();
+    let secret: String = Base16EncodedBytes::new(dpi.w.to_bytes().as_slice()).into();
+    let address = SecretKey::DlogSecretKey(dpi).get_address_from_public_image();
+    let own_address = AddressEncoder::new(NetworkPrefix::Mainnet).address_to_str(&address);
+    let min = u64::from(BoxValue::SAFE_USER_MIN);
+
+    let input = ErgoBox::from_box_candidate(
+        &ErgoBoxCandidate {
+            value: BoxValue::new(min * 4).unwrap(),
+            ergo_tree: Contract::pay_to_address(&address).unwrap().ergo_tree(),
+            tokens: vec![],
+            additional_registers: NonMandatoryRegisters::empty(),
+            creation_height: 0,
+        },
+        TxId::zero(), 0);
+    let js_input_box = JsValue::from_serde(&input).unwrap();
+
+    // parent sends everything back to own address
+    let parent = Transaction::create(
+        vec![JsValue::from_serde(&TxInput {
+            box_id: Base16EncodedBytes::new(&input.box_id().0 .0[..]).into(),
+        }).unwrap()].into_boxed_slice(),
+        vec![JsValue::from_serde(&TxOutput {
+            registers: Default::default(),
+            assets: vec![],
+            value: (min * 3).to_string(),
+            address: own_address.clone(),
+        }).unwrap()].into_boxed_slice(),
+        min, 0).unwrap();
+    let parent = Transaction::sign(
+        vec![JsValue::from_serde(&secret).unwrap()].into_boxed_slice(),
+        vec![js_input_box.clone()].into_boxed_slice(),
+        &parent.to_json().unwrap(),
+    ).unwrap();
+
+    // child spends unconfirmed parent output
+    let child = Transaction::create_chained(
+        &parent,
+        vec![js_input_box].into_boxed_slice(),
+        vec![JsValue::from_serde(&TxOutput {
+            registers: Default::default(),
+            assets: vec![],
+            value: min.to_string(),
+            address: "9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV".to_string(),
+        }).unwrap()].into_boxed_slice(),
+        min,
+        &own_address,
+        0).unwrap();
+
+    let signed = Transaction::sign_chained(
+        vec![JsValue::from_serde(&secret).unwrap()].into_boxed_slice(),
+        &parent,
+        vec![].into_boxed_slice(),
+        &child,
+    ).unwrap();
+    assert_eq!(signed.outputs_len(), 3);
+
+    // malformed outputs are reported as errors
+    let bad_outputs = [
+        TxOutput {
+            registers: Default::default(),
+            assets: vec![],
+            value: min.to_string(),
+            address: "9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9Uwptg".to_string(),
+        },
+        TxOutput {
+            registers: Default::default(),
+            assets: vec![],
+            value: "not a number".to_string(),
+            address: own_address.clone(),
+        },
+        TxOutput {
+            registers: Default::default(),
+            assets: vec![AssetValue { token_id: "abc".to_string(), amount: "1".to_string() }],
+            value: min.to_string(),
+            address: own_address.clone(),
+        },
+    ];
+    for output in bad_outputs.iter() {
+        let result = Transaction::create_chained(
+            &parent,
+            vec![].into_boxed_slice(),
+            vec![JsValue::from_serde(output).unwrap()].into_boxed_slice(),
+            min,
+            &own_address,
+            0);
+        assert!(result.is_err());
+    }
+}
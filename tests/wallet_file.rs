@@ -0,0 +1,56 @@
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+extern crate wasm_bindgen;
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
+
+use ergowallet_wasm::*;
+
+const PASSWORD: &str = "Ergo Wallet Password!";
+const MNEMONIC: &str = "slow silly start wash bundle suffer bulb ancient height spin express remind today effort helmet";
+
+#[wasm_bindgen_test]
+fn wallet_file_open_success() {
+    let secret = serde_json::json!({
+        "mnemonic": MNEMONIC,
+        "accounts": [0, 1],
+        "labels": {"9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV": "Savings"},
+        "network": "mainnet"
+    });
+    let file = create_wallet_file("My Wallet", PASSWORD, &JsValue::from_serde(&secret).unwrap(), &JsValue::UNDEFINED).unwrap();
+
+    let parsed: WalletFile = file.into_serde().unwrap();
+    assert_eq!(parsed.name, "My Wallet");
+    assert!(!parsed.secret.contains(&hex_of(MNEMONIC)));
+
+    let manager = KeyManager::open(&file, PASSWORD).unwrap();
+    assert_eq!(manager.name(), "My Wallet");
+    assert_eq!(manager.network(), "mainnet");
+    assert_eq!(manager.accounts(), vec![0, 1]);
+
+    assert!(KeyManager::open(&file, "Invalid Password").is_err());
+}
+
+fn hex_of(s: &str) -> String {
+    s.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[wasm_bindgen_test]
+fn wallet_file_tampered_header() {
+    let secret = serde_json::json!({"mnemonic": MNEMONIC, "accounts": [0], "network": "mainnet"});
+    let file = create_wallet_file("My Wallet", PASSWORD, &JsValue::from_serde(&secret).unwrap(), &JsValue::UNDEFINED).unwrap();
+
+    let parsed: WalletFile = file.into_serde().unwrap();
+    assert_eq!(parsed.version, WALLET_FILE_VERSION);
+
+    let mut tampered = parsed.clone();
+    tampered.name = "Other Wallet".to_string();
+    assert!(KeyManager::open(&JsValue::from_serde(&tampered).unwrap(), PASSWORD).is_err());
+
+    let mut tampered = parsed;
+    tampered.version += 1;
+    assert!(KeyManager::open(&JsValue::from_serde(&tampered).unwrap(), PASSWORD).is_err());
+}
@@ -7,10 +7,9 @@ extern crate wasm_bindgen_test;
 
 use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
+use gloo_utils::format::JsValueSerdeExt;
 use web_sys::console;
 
-use ergowallet_wasm::*;
-use ergo_lib::chain::ergo_box::BoxValue;
 use ergo_lib::chain::contract::Contract;
 use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
 use ergo_lib::ergotree_ir::address::{AddressEncoder, NetworkPrefix};